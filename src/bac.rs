@@ -0,0 +1,516 @@
+use rand_core::{CryptoRng, OsRng, RngCore};
+use sha1::{Digest, Sha1};
+use zeroize::Zeroizing;
+
+use super::mac::{constant_time_eq, retail_mac_padded, MacPadding};
+use super::modes::{cbc_decode, cbc_encode, xor};
+use super::{Block, Error, Result, TdesKey};
+
+/// longest READ BINARY answer whose protected response (DO'87', DO'99' and DO'8E')
+/// still fits 256 bytes of a short response APDU
+const MAX_READ_LENGTH: usize = 231;
+
+/// document number (padded to 9 chars with '<'), date of birth and date of expiry
+/// (YYMMDD), each followed by its check digit
+pub fn mrz_information(
+    document_number: &str,
+    date_of_birth: &str,
+    date_of_expiry: &str,
+) -> Result<String> {
+    if document_number.len() > 9 || date_of_birth.len() != 6 || date_of_expiry.len() != 6 {
+        return Err(Error::StringParseError(format!(
+            "{document_number} {date_of_birth} {date_of_expiry}"
+        )));
+    }
+    let document_number = format!("{document_number:<<9}");
+    let mut information = String::with_capacity(24);
+    for field in [document_number.as_str(), date_of_birth, date_of_expiry] {
+        information.push_str(field);
+        information.push(check_digit(field)?);
+    }
+    Ok(information)
+}
+
+/// ICAO 9303 check digit: weights 7, 3, 1, letters are 10..=35 and '<' is 0
+pub fn check_digit(field: &str) -> Result<char> {
+    let mut sum = 0;
+    for (ch, weight) in field.chars().zip([7, 3, 1].into_iter().cycle()) {
+        let value = match ch {
+            '0'..='9' | 'A'..='Z' => ch.to_digit(36).unwrap_or_default(),
+            '<' => 0,
+            _ => return Err(Error::StringParseError(field.to_string())),
+        };
+        sum += value * weight;
+    }
+    Ok(char::from(b'0' + (sum % 10) as u8))
+}
+
+/// 3DES encryption and MAC keys
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct BacKeys {
+    pub enc: TdesKey,
+    pub mac: TdesKey,
+}
+
+impl BacKeys {
+    /// document basic access keys from MRZ information
+    pub fn from_mrz(mrz_information: &str) -> Result<Self> {
+        let digest = Zeroizing::new(Sha1::digest(mrz_information.as_bytes()).to_vec());
+        Self::from_seed(&digest[..16])
+    }
+
+    /// parity adjusted leftmost 16 bytes of SHA-1 over the key seed and a 4 byte
+    /// counter, 1 for encryption and 2 for MAC key
+    pub fn from_seed(key_seed: &[u8]) -> Result<Self> {
+        if key_seed.len() != 16 {
+            return Err(Error::InvalidKeyLength(key_seed.len() * 8));
+        }
+        let derive = |counter: u8| -> Result<TdesKey> {
+            let mut hasher = Sha1::new();
+            hasher.update(key_seed);
+            hasher.update([0, 0, 0, counter]);
+            let digest = Zeroizing::new(hasher.finalize().to_vec());
+            Ok(TdesKey::from_bytes(&digest[..16])?.with_odd_parity())
+        };
+        Ok(Self {
+            enc: derive(1)?,
+            mac: derive(2)?,
+        })
+    }
+}
+
+/// RND.IC, RND.IFD and K.IFD
+type Exchange = ([u8; 8], [u8; 8], Zeroizing<[u8; 16]>);
+
+/// terminal (IFD) side of the mutual authentication
+#[derive(Debug)]
+pub struct BacTerminal {
+    keys: BacKeys,
+    state: Option<Exchange>,
+}
+
+impl BacTerminal {
+    pub fn new(keys: BacKeys) -> Self {
+        Self { keys, state: None }
+    }
+
+    /// returns MUTUAL AUTHENTICATE data for the chip challenge, RND.IFD and K.IFD
+    /// are taken from OS randomness
+    pub fn authenticate(&mut self, rnd_ic: &[u8; 8]) -> Result<Vec<u8>> {
+        self.authenticate_with(rnd_ic, &mut OsRng)
+    }
+
+    /// same as authenticate, but takes RND.IFD and K.IFD from rng
+    pub fn authenticate_with<R: RngCore + CryptoRng>(
+        &mut self,
+        rnd_ic: &[u8; 8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>> {
+        let mut rnd_ifd = [0; 8];
+        let mut k_ifd = Zeroizing::new([0; 16]);
+        rng.try_fill_bytes(&mut rnd_ifd)
+            .and_then(|()| rng.try_fill_bytes(&mut k_ifd[..]))
+            .map_err(|err| Error::RandomSource(err.to_string()))?;
+
+        let data = encrypt_and_mac(&self.keys, &rnd_ifd, rnd_ic, &k_ifd[..])?;
+        self.state = Some((*rnd_ic, rnd_ifd, k_ifd));
+        Ok(data)
+    }
+
+    /// checks chip response and returns secure messaging with session keys
+    pub fn complete(&mut self, response: &[u8]) -> Result<SecureMessaging> {
+        let (rnd_ic, rnd_ifd, k_ifd) = self.state.take().ok_or(Error::AuthenticationFailed)?;
+        let plain = verify_and_decrypt(&self.keys, response)?;
+        if !constant_time_eq(&plain[..8], &rnd_ic) || !constant_time_eq(&plain[8..16], &rnd_ifd) {
+            return Err(Error::AuthenticationFailed);
+        }
+        SecureMessaging::from_exchange(&k_ifd[..], &plain[16..], &rnd_ic, &rnd_ifd)
+    }
+}
+
+/// eMRTD chip with elementary files, answers authentication and secure messaging
+/// commands
+#[derive(Debug)]
+pub struct SimulatedChip {
+    keys: BacKeys,
+    files: Vec<(u16, Vec<u8>)>,
+    rnd_ic: Option<[u8; 8]>,
+    session: Option<SecureMessaging>,
+    selected: Option<usize>,
+}
+
+impl SimulatedChip {
+    pub fn new(keys: BacKeys) -> Self {
+        Self {
+            keys,
+            files: Vec::new(),
+            rnd_ic: None,
+            session: None,
+            selected: None,
+        }
+    }
+
+    /// adds elementary file readable after authentication
+    pub fn with_file(mut self, file_id: u16, content: Vec<u8>) -> Self {
+        self.files.push((file_id, content));
+        self
+    }
+
+    /// GET CHALLENGE, RND.IC is taken from OS randomness
+    pub fn get_challenge(&mut self) -> Result<[u8; 8]> {
+        self.get_challenge_with(&mut OsRng)
+    }
+
+    /// same as get_challenge, but takes RND.IC from rng
+    pub fn get_challenge_with<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<[u8; 8]> {
+        let mut rnd_ic = [0; 8];
+        rng.try_fill_bytes(&mut rnd_ic)
+            .map_err(|err| Error::RandomSource(err.to_string()))?;
+        self.session = None;
+        self.rnd_ic = Some(rnd_ic);
+        Ok(rnd_ic)
+    }
+
+    /// MUTUAL AUTHENTICATE, K.IC is taken from OS randomness
+    pub fn mutual_authenticate(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.mutual_authenticate_with(data, &mut OsRng)
+    }
+
+    /// same as mutual_authenticate, but takes K.IC from rng
+    pub fn mutual_authenticate_with<R: RngCore + CryptoRng>(
+        &mut self,
+        data: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>> {
+        let rnd_ic = self.rnd_ic.take().ok_or(Error::AuthenticationFailed)?;
+        let plain = verify_and_decrypt(&self.keys, data)?;
+        if !constant_time_eq(&plain[8..16], &rnd_ic) {
+            return Err(Error::AuthenticationFailed);
+        }
+        let mut rnd_ifd = [0; 8];
+        rnd_ifd.copy_from_slice(&plain[..8]);
+        let mut k_ic = Zeroizing::new([0; 16]);
+        rng.try_fill_bytes(&mut k_ic[..])
+            .map_err(|err| Error::RandomSource(err.to_string()))?;
+
+        let response = encrypt_and_mac(&self.keys, &rnd_ic, &rnd_ifd, &k_ic[..])?;
+        self.session = Some(SecureMessaging::from_exchange(
+            &plain[16..],
+            &k_ic[..],
+            &rnd_ic,
+            &rnd_ifd,
+        )?);
+        self.selected = None;
+        Ok(response)
+    }
+
+    /// answers protected SELECT (by file id) and READ BINARY, any secure messaging
+    /// error ends the session with 6988
+    pub fn process(&mut self, apdu: &[u8]) -> Result<Vec<u8>> {
+        let Some(session) = self.session.as_mut() else {
+            return Ok(vec![0x69, 0x82]);
+        };
+        let Ok(command) = session.unprotect_command(apdu) else {
+            self.session = None;
+            return Ok(vec![0x69, 0x88]);
+        };
+
+        let (data, sw) = match (command[1], command.get(4..)) {
+            (0xA4, Some([2, high, low])) => {
+                let file_id = u16::from_be_bytes([*high, *low]);
+                self.selected = self.files.iter().position(|(id, _)| *id == file_id);
+                match self.selected {
+                    Some(_) => (Vec::new(), [0x90, 0x00]),
+                    None => (Vec::new(), [0x6A, 0x82]),
+                }
+            }
+            (0xB0, Some([le])) => {
+                let le = match *le {
+                    0 => 256,
+                    le => le as usize,
+                };
+                self.read_binary(command[2], command[3], le)
+            }
+            _ => (Vec::new(), [0x6D, 0x00]),
+        };
+        match self.session.as_mut() {
+            Some(session) => session.protect_response(&data, sw),
+            None => Ok(vec![0x69, 0x82]),
+        }
+    }
+
+    /// P1 bit 8 set selects the file by short EF identifier with P2 as offset,
+    /// otherwise P1 and P2 are the offset in the selected file
+    fn read_binary(&mut self, p1: u8, p2: u8, le: usize) -> (Vec<u8>, [u8; 2]) {
+        let offset = match p1 & 0xE0 {
+            0x80 => {
+                // short EF identifier of EF 01xx is its low byte
+                let file_id = 0x0100 | (p1 & 0x1F) as u16;
+                self.selected = self.files.iter().position(|(id, _)| *id == file_id);
+                if self.selected.is_none() {
+                    return (Vec::new(), [0x6A, 0x82]);
+                }
+                p2 as usize
+            }
+            0x00..=0x60 => u16::from_be_bytes([p1, p2]) as usize,
+            _ => return (Vec::new(), [0x6A, 0x86]),
+        };
+        match self.selected.map(|index| &self.files[index].1) {
+            Some(content) if offset < content.len() => {
+                let end = content.len().min(offset + le.min(MAX_READ_LENGTH));
+                (content[offset..end].to_vec(), [0x90, 0x00])
+            }
+            Some(_) => (Vec::new(), [0x6B, 0x00]),
+            None => (Vec::new(), [0x69, 0x86]),
+        }
+    }
+}
+
+/// secure messaging with session keys and send sequence counter, each protected
+/// command and response increments the counter
+#[derive(Debug, Clone)]
+pub struct SecureMessaging {
+    keys: BacKeys,
+    ssc: u64,
+}
+
+impl SecureMessaging {
+    pub fn new(keys: BacKeys, ssc: [u8; 8]) -> Self {
+        Self {
+            keys,
+            ssc: u64::from_be_bytes(ssc),
+        }
+    }
+
+    /// session keys are derived from K.IFD xor K.IC, SSC is the least significant
+    /// halves of RND.IC and RND.IFD
+    fn from_exchange(
+        k_ifd: &[u8],
+        k_ic: &[u8],
+        rnd_ic: &[u8; 8],
+        rnd_ifd: &[u8; 8],
+    ) -> Result<Self> {
+        let key_seed = Zeroizing::new(xor(k_ifd, k_ic));
+        let mut ssc = [0; 8];
+        ssc[..4].copy_from_slice(&rnd_ic[4..]);
+        ssc[4..].copy_from_slice(&rnd_ifd[4..]);
+        Ok(Self::new(BacKeys::from_seed(&key_seed)?, ssc))
+    }
+
+    pub fn ssc(&self) -> [u8; 8] {
+        self.ssc.to_be_bytes()
+    }
+
+    /// protects short APDU with DO'87' (encrypted data), DO'97' (Le) and DO'8E' (MAC)
+    pub fn protect_command(&mut self, apdu: &[u8]) -> Result<Vec<u8>> {
+        let (header, data, le) = split_apdu(apdu)?;
+        let header = [header[0] | 0x0C, header[1], header[2], header[3]];
+
+        let mut objects = Vec::new();
+        if !data.is_empty() {
+            let mut value = vec![0x01];
+            value.extend(self.encrypt(data)?);
+            objects.extend(tlv(0x87, &value)?);
+        }
+        if !le.is_empty() {
+            objects.extend(tlv(0x97, le)?);
+        }
+        let mut mac_data = MacPadding::Method2.pad(&header);
+        mac_data.extend(&objects);
+        objects.extend(tlv(0x8E, &self.mac(&mac_data)?)?);
+
+        let mut protected = header.to_vec();
+        protected.push(byte_len(objects.len())?);
+        protected.extend(objects);
+        protected.push(0x00);
+        Ok(protected)
+    }
+
+    /// verifies MAC of protected response and returns its data followed by status word
+    pub fn unprotect_response(&mut self, response: &[u8]) -> Result<Vec<u8>> {
+        let (objects, sw) = response
+            .split_at_checked(response.len().saturating_sub(2))
+            .filter(|(_, sw)| sw.len() == 2)
+            .ok_or_else(|| Error::InvalidApdu("response is too short".to_string()))?;
+        let (data, status) = self.verify_objects(objects, &[], 0x99)?;
+        let mut result = data;
+        result.extend(status.as_deref().unwrap_or(sw));
+        Ok(result)
+    }
+
+    /// chip side: verifies MAC of protected command and returns the plain one
+    pub fn unprotect_command(&mut self, apdu: &[u8]) -> Result<Vec<u8>> {
+        let (header, body, _) = split_apdu(apdu)?;
+        let padded_header = MacPadding::Method2.pad(header);
+        let (data, le) = self.verify_objects(body, &padded_header, 0x97)?;
+
+        let mut command = vec![header[0] & !0x0C, header[1], header[2], header[3]];
+        if !data.is_empty() {
+            command.push(byte_len(data.len())?);
+            command.extend(&data);
+        }
+        command.extend(le.unwrap_or_default());
+        Ok(command)
+    }
+
+    /// chip side: protects response data with DO'87', DO'99' (status word) and DO'8E'
+    pub fn protect_response(&mut self, data: &[u8], sw: [u8; 2]) -> Result<Vec<u8>> {
+        let mut objects = Vec::new();
+        if !data.is_empty() {
+            let mut value = vec![0x01];
+            value.extend(self.encrypt(data)?);
+            objects.extend(tlv(0x87, &value)?);
+        }
+        objects.extend(tlv(0x99, &sw)?);
+        let mac = self.mac(&objects)?;
+        objects.extend(tlv(0x8E, &mac)?);
+        objects.extend(sw);
+        Ok(objects)
+    }
+
+    /// checks DO'8E' over prefix and preceding objects, returns decrypted DO'87' and
+    /// value of the other allowed object
+    fn verify_objects(
+        &mut self,
+        objects: &[u8],
+        prefix: &[u8],
+        other_tag: u8,
+    ) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+        let mut encrypted = None;
+        let mut other = None;
+        let mut rest = objects;
+        loop {
+            let consumed = objects.len() - rest.len();
+            let (tag, value, tail) = parse_tlv(rest)?;
+            match tag {
+                0x87 if value.first() == Some(&0x01) => encrypted = Some(&value[1..]),
+                0x8E => {
+                    let mut mac_data = prefix.to_vec();
+                    mac_data.extend(&objects[..consumed]);
+                    let expected = self.mac(&mac_data)?;
+                    // trailing bytes may only be Le of the command
+                    if !constant_time_eq(&expected, value) || tail.len() > 1 {
+                        return Err(Error::MacMismatch);
+                    }
+                    break;
+                }
+                tag if tag == other_tag => other = Some(value.to_vec()),
+                _ => return Err(Error::InvalidApdu(format!("unexpected object {tag:02X}"))),
+            }
+            rest = tail;
+        }
+        let data = match encrypted {
+            Some(encrypted) => self.decrypt(encrypted)?,
+            None => Vec::new(),
+        };
+        Ok((data, other))
+    }
+
+    /// increments SSC and computes retail MAC over it and data
+    fn mac(&mut self, data: &[u8]) -> Result<[u8; 8]> {
+        self.ssc = self.ssc.wrapping_add(1);
+        let mut mac_data = self.ssc.to_be_bytes().to_vec();
+        mac_data.extend(data);
+        retail_mac_padded(&self.keys.mac, &MacPadding::Method2.pad(&mac_data))
+    }
+
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let padded = Zeroizing::new(MacPadding::Method2.pad(data));
+        cbc_encode(&self.keys.enc, &Block::from_bytes(&[0; 8])?, &padded)
+    }
+
+    /// decrypts and removes ISO 9797-1 method 2 padding
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut plain = cbc_decode(&self.keys.enc, &Block::from_bytes(&[0; 8])?, data)?;
+        let end = plain
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .filter(|end| plain[*end] == 0x80)
+            .ok_or_else(|| Error::InvalidApdu("invalid padding".to_string()))?;
+        plain.truncate(end);
+        Ok(plain)
+    }
+}
+
+/// S is the first random, the second one and the key part, returns its encoding
+/// followed by MAC
+fn encrypt_and_mac(keys: &BacKeys, first: &[u8], second: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    let mut s = Zeroizing::new(first.to_vec());
+    s.extend(second);
+    s.extend(key);
+    let mut data = cbc_encode(&keys.enc, &Block::from_bytes(&[0; 8])?, &s)?;
+    let mac = retail_mac_padded(&keys.mac, &MacPadding::Method2.pad(&data))?;
+    data.extend(mac);
+    Ok(data)
+}
+
+/// checks MAC of 40 byte authentication data and returns decrypted 32 bytes
+fn verify_and_decrypt(keys: &BacKeys, data: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    if data.len() != 40 {
+        return Err(Error::AuthenticationFailed);
+    }
+    let (encrypted, mac) = data.split_at(32);
+    if !constant_time_eq(
+        &retail_mac_padded(&keys.mac, &MacPadding::Method2.pad(encrypted))?,
+        mac,
+    ) {
+        return Err(Error::AuthenticationFailed);
+    }
+    Ok(Zeroizing::new(cbc_decode(
+        &keys.enc,
+        &Block::from_bytes(&[0; 8])?,
+        encrypted,
+    )?))
+}
+
+/// returns header, data and optional Le of a short APDU
+fn split_apdu(apdu: &[u8]) -> Result<(&[u8], &[u8], &[u8])> {
+    let invalid = || Error::InvalidApdu(format!("invalid length {}", apdu.len()));
+    let (header, body) = apdu.split_at_checked(4).ok_or_else(invalid)?;
+    match body {
+        [] => Ok((header, &[], &[])),
+        [_] => Ok((header, &[], body)),
+        [lc, rest @ ..] if rest.len() == *lc as usize => Ok((header, rest, &[])),
+        [lc, rest @ ..] if rest.len() == *lc as usize + 1 => {
+            let (data, le) = rest.split_at(*lc as usize);
+            Ok((header, data, le))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// data object with BER length up to 65535
+fn tlv(tag: u8, value: &[u8]) -> Result<Vec<u8>> {
+    let mut object = vec![tag];
+    match value.len() {
+        len @ 0..=0x7F => object.push(len as u8),
+        len @ 0x80..=0xFF => object.extend([0x81, len as u8]),
+        len => {
+            let len = u16::try_from(len)
+                .map_err(|_| Error::InvalidApdu(format!("data is too long ({len} bytes)")))?;
+            object.push(0x82);
+            object.extend(len.to_be_bytes());
+        }
+    }
+    object.extend(value);
+    Ok(object)
+}
+
+/// returns tag, value and the rest of data
+fn parse_tlv(data: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let invalid = || Error::InvalidApdu("truncated data object".to_string());
+    let (tag, len, rest) = match data {
+        [tag, 0x81, len, rest @ ..] => (*tag, *len as usize, rest),
+        [tag, 0x82, high, low, rest @ ..] => {
+            (*tag, u16::from_be_bytes([*high, *low]) as usize, rest)
+        }
+        [tag, len, rest @ ..] if *len <= 0x7F => (*tag, *len as usize, rest),
+        _ => return Err(invalid()),
+    };
+    let (value, rest) = rest.split_at_checked(len).ok_or_else(invalid)?;
+    Ok((tag, value, rest))
+}
+
+fn byte_len(len: usize) -> Result<u8> {
+    u8::try_from(len).map_err(|_| Error::InvalidApdu(format!("data is too long ({len} bytes)")))
+}
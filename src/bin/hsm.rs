@@ -0,0 +1,20 @@
+use std::env;
+use std::net::TcpListener;
+
+use des_ndtp::hsm::Hsm;
+use des_ndtp::{Error, FromHexStr, Result, TdesKey};
+
+/// usage: hsm [address], LMK is read from HSM_LMK (hex) or generated
+fn main() -> Result<()> {
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:1500".to_string());
+    let lmk = match env::var("HSM_LMK") {
+        Ok(lmk) => TdesKey::from_hex_str(&lmk)?,
+        Err(_) => TdesKey::generate(3)?,
+    };
+
+    let listener = TcpListener::bind(&address).map_err(|err| Error::Io(err.to_string()))?;
+    println!("listening on {address}, LMK check value {}", lmk.kcv()?);
+    Hsm::new(lmk).serve(&listener)
+}
@@ -1,10 +1,10 @@
 use bitvec::prelude::*;
 use itertools::Itertools;
-use std::mem::swap;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 use super::traits::{FromHexStr, ToHexString};
-use super::{Error, MainKey, Result, ShiftDirection, ShiftSchemes};
+use super::{permute, DesTables, Error, Feistel, MainKey, Result, ShiftDirection, Subkey};
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -84,6 +84,29 @@ impl Block {
         Ok(Self { data })
     }
 
+    /// passed slice should be 8 bytes long, bits are taken most significant first
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 8 {
+            return Err(Error::InvalidIterableLength {
+                expected: 8,
+                got: bytes.len(),
+            });
+        }
+        let data = bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1))
+            .collect();
+        Ok(Self { data })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let mut result = [0; 8];
+        for (byte, chunk) in result.iter_mut().zip(self.data.chunks(8)) {
+            *byte = chunk.iter().fold(0, |acc, bit| acc << 1 | *bit as u8);
+        }
+        result
+    }
+
     pub fn as_bitvec(&self) -> &BitVec {
         &self.data
     }
@@ -96,54 +119,111 @@ impl Block {
     }
 
     pub fn encode(&self, key: &MainKey) -> Result<Self> {
-        let data = ShiftSchemes::IP.shift(self.as_bitvec().to_owned())?;
-        let (left, right) = data.split_at(32);
-        let mut left = left.to_owned();
-        let mut right = right.to_owned();
-        for round in 1..=16 {
-            left ^= self.f(
-                right.clone(),
-                key.get_round_key(round, ShiftDirection::Left)?,
-            )?;
-            swap(&mut left, &mut right);
-        }
-        right.extend(left);
-        Self::new(ShiftSchemes::IP1.shift(right)?)
+        self.encode_rounds(key, 1..=16)
     }
 
     pub fn decode(&self, key: &MainKey) -> Result<Self> {
-        let data = ShiftSchemes::IP.shift(self.as_bitvec().to_owned())?;
-        let (left, right) = data.split_at(32);
-        let mut left = left.to_owned();
-        let mut right = right.to_owned();
-
-        for round in 1..=16 {
-            left ^= self.f(
-                right.clone(),
-                key.get_round_key(round, ShiftDirection::Right)?,
-            )?;
-            swap(&mut left, &mut right);
+        self.decode_rounds(key, 1..=16)
+    }
+
+    /// runs only the given rounds (1 <= start <= end <= 16), e.g. 1..=4 for 4-round DES.
+    /// IP and IP1 are still applied
+    pub fn encode_rounds(&self, key: &MainKey, rounds: RangeInclusive<u8>) -> Result<Self> {
+        self.encode_with_tables(key, &DesTables::default(), rounds)
+    }
+
+    /// inverse of encode_rounds called with the same rounds
+    pub fn decode_rounds(&self, key: &MainKey, rounds: RangeInclusive<u8>) -> Result<Self> {
+        self.decode_with_tables(key, &DesTables::default(), rounds)
+    }
+
+    /// DES variant driven by custom tables, pass 1..=16 for all rounds
+    pub fn encode_with_tables(
+        &self,
+        key: &MainKey,
+        tables: &DesTables,
+        rounds: RangeInclusive<u8>,
+    ) -> Result<Self> {
+        let subkeys = |round| {
+            key.get_round_key_with_tables(round, ShiftDirection::Left, tables)
+                .map(MainKey::into_bitvec)
+        };
+        self.feistel(tables, rounds, subkeys, false)
+    }
+
+    /// inverse of encode_with_tables called with the same tables and rounds
+    pub fn decode_with_tables(
+        &self,
+        key: &MainKey,
+        tables: &DesTables,
+        rounds: RangeInclusive<u8>,
+    ) -> Result<Self> {
+        let subkeys = |round| {
+            key.get_round_key_with_tables(round, ShiftDirection::Left, tables)
+                .map(MainKey::into_bitvec)
+        };
+        self.feistel(tables, rounds, subkeys, true)
+    }
+
+    /// DES with independent subkeys: subkeys[0] is used in round 1 and so on,
+    /// no key schedule is involved
+    pub fn encode_with_subkeys(&self, subkeys: &[Subkey; 16]) -> Result<Self> {
+        let subkeys = |round: u8| Ok(subkeys[round as usize - 1].as_bitvec().clone());
+        self.feistel(&DesTables::default(), 1..=16, subkeys, false)
+    }
+
+    /// inverse of encode_with_subkeys called with the same subkeys
+    pub fn decode_with_subkeys(&self, subkeys: &[Subkey; 16]) -> Result<Self> {
+        let subkeys = |round: u8| Ok(subkeys[round as usize - 1].as_bitvec().clone());
+        self.feistel(&DesTables::default(), 1..=16, subkeys, true)
+    }
+
+    fn check_rounds(rounds: &RangeInclusive<u8>) -> Result<()> {
+        let (start, end) = (*rounds.start(), *rounds.end());
+        if start < 1 || end > 16 || start > end {
+            return Err(Error::InvalidRoundRange { start, end });
         }
-        right.extend(left);
-        Self::new(ShiftSchemes::IP1.shift(right)?)
+        Ok(())
+    }
+
+    /// subkeys gets round number and returns its 48-bit key
+    fn feistel(
+        &self,
+        tables: &DesTables,
+        rounds: RangeInclusive<u8>,
+        subkeys: impl Fn(u8) -> Result<BitVec>,
+        decode: bool,
+    ) -> Result<Self> {
+        tables.validate()?;
+        Self::check_rounds(&rounds)?;
+        let network = Feistel::new(
+            32,
+            rounds,
+            |right, round_key| Self::f(right, round_key, tables),
+            subkeys,
+        );
+
+        let data = permute(&tables.ip, self.as_bitvec().to_owned())?;
+        let data = match decode {
+            false => network.encode(&data)?,
+            true => network.decode(&data)?,
+        };
+        Self::new(permute(&tables.ip1, data)?)
     }
 
-    fn f(&self, right: BitVec, key: MainKey) -> Result<BitVec> {
-        let key = key.into_bitvec();
-        let right = ShiftSchemes::E.shift(right)? ^ key;
+    fn f(right: &BitVec, key: &BitVec, tables: &DesTables) -> Result<BitVec> {
+        let right = permute(&tables.e, right.clone())? ^ key;
 
         let blocks = right.chunks(6).map(|it| it.to_owned());
-        let schemes = ShiftSchemes::get_s_schemes();
         let right = blocks
-            .zip(schemes)
-            .map(|(block, scheme)| Self::block_s_scheme_shift(block, *scheme))
+            .zip(&tables.s_boxes)
+            .map(|(block, s_box)| Self::block_s_scheme_shift(block, s_box))
             .concat();
 
-        ShiftSchemes::P.shift(right)
+        permute(&tables.p, right)
     }
 
-    fn block_s_scheme_shift(block: BitVec, s_scheme: ShiftSchemes) -> BitVec {
-        let scheme = s_scheme.as_slice();
+    fn block_s_scheme_shift(block: BitVec, scheme: &[usize]) -> BitVec {
         let pos = Self::block_to_pos(block) as usize;
         let block = BitVec::from_element(scheme[pos]);
         let (block, _) = block.split_at(4);
@@ -171,6 +251,7 @@ impl Block {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ShiftSchemes;
 
     #[test]
     fn test_block_to_pos() -> Result<()> {
@@ -196,7 +277,7 @@ mod tests {
     fn test_block_s_shift() -> Result<()> {
         let scheme = ShiftSchemes::S3;
         let block = MainKey::from_str("110010")?.into_bitvec();
-        let block = Block::block_s_scheme_shift(block, scheme);
+        let block = Block::block_s_scheme_shift(block, scheme.as_slice());
         assert_eq!(block, MainKey::from_str("0001")?.into_bitvec());
 
         Ok(())
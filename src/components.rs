@@ -0,0 +1,140 @@
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::kcv::{kcv, KcvMethod};
+use super::mac::constant_time_eq;
+use super::{BlockCipher, Error, MainKey, Result, TdesKey};
+
+/// keys that can be split into XOR components
+pub trait KeyMaterial: BlockCipher + Sized {
+    fn to_key_bytes(&self) -> Vec<u8>;
+    /// sets odd parity on the result
+    fn from_key_bytes(bytes: &[u8]) -> Result<Self>;
+    fn has_odd_parity(&self) -> bool;
+}
+
+impl KeyMaterial for MainKey {
+    fn to_key_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_key_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::from_bytes(bytes).with_odd_parity())
+    }
+
+    fn has_odd_parity(&self) -> bool {
+        MainKey::has_odd_parity(self)
+    }
+}
+
+impl KeyMaterial for TdesKey {
+    fn to_key_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_key_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::from_bytes(bytes)?.with_odd_parity())
+    }
+
+    fn has_odd_parity(&self) -> bool {
+        TdesKey::has_odd_parity(self)
+    }
+}
+
+/// one custodian's share of a key together with its 6 digit check value
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyComponent<K> {
+    key: K,
+    kcv: String,
+}
+
+impl<K: KeyMaterial> KeyComponent<K> {
+    /// checks the component's parity and its check value (any length up to 16 digits)
+    pub fn new(key: K, expected_kcv: &str) -> Result<Self> {
+        let component = Self::from_key(key)?;
+        check_kcv(&component.key, expected_kcv)?;
+        Ok(component)
+    }
+
+    /// checks the component's parity and computes its check value
+    pub fn from_key(key: K) -> Result<Self> {
+        if !key.has_odd_parity() {
+            return Err(Error::InvalidParity);
+        }
+        let kcv = kcv(&key, KcvMethod::Legacy, 6)?;
+        Ok(Self { key, kcv })
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn kcv(&self) -> &str {
+        &self.kcv
+    }
+}
+
+/// splits key into `count` (at least 2) random components XORing back to it
+pub fn split<K: KeyMaterial, R: RngCore + CryptoRng>(
+    key: &K,
+    count: usize,
+    rng: &mut R,
+) -> Result<Vec<KeyComponent<K>>> {
+    if count < 2 {
+        return Err(Error::InvalidIterableLength {
+            expected: 2,
+            got: count,
+        });
+    }
+    if !key.has_odd_parity() {
+        return Err(Error::InvalidParity);
+    }
+    // running XOR of the key with the components drawn so far
+    let mut rest = Zeroizing::new(key.to_key_bytes());
+    let mut components = Vec::with_capacity(count);
+    for _ in 1..count {
+        let mut bytes = Zeroizing::new(vec![0; rest.len()]);
+        rng.try_fill_bytes(&mut bytes)
+            .map_err(|err| Error::RandomSource(err.to_string()))?;
+        let component = K::from_key_bytes(&bytes)?;
+        xor_into(&mut rest, &Zeroizing::new(component.to_key_bytes()));
+        components.push(KeyComponent::from_key(component)?);
+    }
+    // parity bits are dropped by DES, so fixing them keeps the combined key intact
+    components.push(KeyComponent::from_key(K::from_key_bytes(&rest)?)?);
+    Ok(components)
+}
+
+/// XORs at least 2 components together and accepts the result only if its check value
+/// matches
+pub fn combine<K: KeyMaterial>(components: &[KeyComponent<K>], expected_kcv: &str) -> Result<K> {
+    if components.len() < 2 {
+        return Err(Error::InvalidIterableLength {
+            expected: 2,
+            got: components.len(),
+        });
+    }
+    let (first, others) = (&components[0], &components[1..]);
+    let mut bytes = Zeroizing::new(first.key.to_key_bytes());
+    for component in others {
+        xor_into(&mut bytes, &Zeroizing::new(component.key.to_key_bytes()));
+    }
+    let key = K::from_key_bytes(&bytes)?;
+    check_kcv(&key, expected_kcv)?;
+    Ok(key)
+}
+
+fn check_kcv(key: &impl BlockCipher, expected: &str) -> Result<()> {
+    let actual = kcv(key, KcvMethod::Legacy, expected.len())?;
+    if !constant_time_eq(actual.as_bytes(), expected.to_uppercase().as_bytes()) {
+        return Err(Error::KcvMismatch {
+            expected: expected.to_string(),
+            got: actual,
+        });
+    }
+    Ok(())
+}
+
+fn xor_into(target: &mut [u8], other: &[u8]) {
+    target.iter_mut().zip(other).for_each(|(a, b)| *a ^= b);
+}
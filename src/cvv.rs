@@ -0,0 +1,81 @@
+use std::ops::RangeInclusive;
+
+use zeroize::Zeroizing;
+
+use super::mac::constant_time_eq;
+use super::modes::xor;
+use super::pin_verification::decimalize;
+use super::traits::{FromHexStr, ToHexString};
+use super::{Block, Error, Result, TdesKey};
+
+/// card verification value over PAN, expiry (YYMM) and 3 digit service code,
+/// CVK pair should be a double-length key
+pub fn cvv(cvk: &TdesKey, pan: &str, expiry: &str, service_code: &str) -> Result<String> {
+    check_digits(pan, 12..=19)?;
+    check_digits(expiry, 4..=4)?;
+    check_digits(service_code, 3..=3)?;
+    calculate(cvk, &format!("{pan}{expiry}{service_code}"))
+}
+
+/// CVV2/CVC2 printed on the card, service code 000
+pub fn cvv2(cvk: &TdesKey, pan: &str, expiry: &str) -> Result<String> {
+    cvv(cvk, pan, expiry, "000")
+}
+
+/// iCVV for the magnetic stripe image of the chip, service code 999
+pub fn icvv(cvk: &TdesKey, pan: &str, expiry: &str) -> Result<String> {
+    cvv(cvk, pan, expiry, "999")
+}
+
+/// dynamic CVV, the application transaction counter (4 decimal digits) follows
+/// the service code
+pub fn dcvv(
+    cvk: &TdesKey,
+    pan: &str,
+    expiry: &str,
+    service_code: &str,
+    atc: u16,
+) -> Result<String> {
+    check_digits(pan, 12..=19)?;
+    check_digits(expiry, 4..=4)?;
+    check_digits(service_code, 3..=3)?;
+    if atc > 9999 {
+        return Err(Error::StringParseError(atc.to_string()));
+    }
+    calculate(cvk, &format!("{pan}{expiry}{service_code}{atc:04}"))
+}
+
+/// compares CVV in constant time
+pub fn verify_cvv(
+    cvk: &TdesKey,
+    pan: &str,
+    expiry: &str,
+    service_code: &str,
+    value: &str,
+) -> Result<bool> {
+    let expected = cvv(cvk, pan, expiry, service_code)?;
+    Ok(constant_time_eq(expected.as_bytes(), value.as_bytes()))
+}
+
+/// digits are zero padded to two blocks: the first one is encoded with CVK A,
+/// xored with the second one and encoded with the CVK pair
+fn calculate(cvk: &TdesKey, digits: &str) -> Result<String> {
+    if cvk.component_count() != 2 {
+        return Err(Error::InvalidKeyLength(cvk.component_count() * 64));
+    }
+    let [key_a, key_b, _] = cvk.components();
+    let data = Zeroizing::new(Vec::from_hex_str(&format!("{digits:0<32}"))?);
+    let (first, second) = data.split_at(8);
+
+    let block = Block::from_bytes(first)?.encode(key_a)?;
+    let block = Block::from_bytes(&xor(&block.to_bytes(), second))?;
+    let result = block.encode(key_a)?.decode(key_b)?.encode(key_a)?;
+    Ok(decimalize(&result.to_bytes().to_upper_hex(), 3))
+}
+
+fn check_digits(digits: &str, lengths: RangeInclusive<usize>) -> Result<()> {
+    if !lengths.contains(&digits.len()) || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err(Error::StringParseError(digits.to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+
+use zeroize::Zeroizing;
+
+use super::modes::xor;
+use super::secret::fmt_redacted;
+use super::traits::{FromHexStr, ToHexString};
+use super::{Block, Error, MainKey, Result, TdesKey};
+
+/// highest value of the 21 bit transaction counter
+pub const MAX_COUNTER: u32 = 0x1F_FFFF;
+
+/// applied to the key (and BDK for the right half of IPEK) in the key generation process
+const KEY_VARIANT: [u8; 16] = [
+    0xC0, 0xC0, 0xC0, 0xC0, 0x00, 0x00, 0x00, 0x00, 0xC0, 0xC0, 0xC0, 0xC0, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// transaction counter values with more ones are skipped
+const MAX_COUNTER_ONES: u32 = 10;
+
+/// 80 bit key serial number: 59 bit initial key serial number followed by 21 bit
+/// transaction counter
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Ksn {
+    bytes: [u8; 10],
+}
+
+impl FromHexStr for Ksn {
+    /// passed str should be 20 hex chars long
+    fn from_hex_str(s: &str) -> Result<Self> {
+        Self::from_bytes(&Vec::from_hex_str(s)?)
+    }
+}
+
+impl ToHexString for Ksn {
+    fn to_upper_hex(&self) -> String {
+        self.bytes.to_upper_hex()
+    }
+
+    fn to_lower_hex(&self) -> String {
+        self.bytes.to_lower_hex()
+    }
+}
+
+impl Ksn {
+    /// passed slice should be 10 bytes long
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes = bytes.try_into().map_err(|_| Error::InvalidIterableLength {
+            expected: 80,
+            got: bytes.len() * 8,
+        })?;
+        Ok(Self { bytes })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 10] {
+        self.bytes
+    }
+
+    pub fn counter(&self) -> u32 {
+        u32::from_be_bytes([0, self.bytes[7], self.bytes[8], self.bytes[9]]) & MAX_COUNTER
+    }
+
+    /// same serial number with the counter replaced
+    pub fn with_counter(&self, counter: u32) -> Result<Self> {
+        if counter > MAX_COUNTER {
+            return Err(Error::CounterExhausted);
+        }
+        let mut bytes = self.bytes;
+        let counter = counter.to_be_bytes();
+        bytes[7] = bytes[7] & 0xE0 | counter[1];
+        bytes[8] = counter[2];
+        bytes[9] = counter[3];
+        Ok(Self { bytes })
+    }
+
+    /// rightmost 64 bits, the input of the key generation process
+    fn register(&self) -> [u8; 8] {
+        let mut register = [0; 8];
+        register.copy_from_slice(&self.bytes[2..]);
+        register
+    }
+}
+
+/// variant applied to the transaction key to get a working key
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum DukptKeyType {
+    Pin,
+    MacRequest,
+    MacResponse,
+    /// data variant encoded by itself
+    DataRequest,
+    /// data variant encoded by itself
+    DataResponse,
+}
+
+impl DukptKeyType {
+    /// byte of each key half which is xored with 0xFF
+    fn variant_byte(&self) -> usize {
+        match self {
+            Self::Pin => 7,
+            Self::MacRequest => 6,
+            Self::MacResponse => 4,
+            Self::DataRequest => 5,
+            Self::DataResponse => 3,
+        }
+    }
+}
+
+/// derives initial PIN encryption key from the base derivation key
+pub fn derive_ipek(bdk: &TdesKey, ksn: &Ksn) -> Result<TdesKey> {
+    let input = Block::from_bytes(&ksn.with_counter(0)?.bytes[..8])?;
+    let bdk_bytes = Zeroizing::new(bdk.to_bytes());
+    let variant = TdesKey::from_bytes(&xor(&bdk_bytes, &KEY_VARIANT))?;
+
+    let mut ipek = Zeroizing::new(bdk.encode(&input)?.to_bytes().to_vec());
+    ipek.extend(variant.encode(&input)?.to_bytes());
+    TdesKey::from_bytes(&ipek)
+}
+
+/// host side derivation of the transaction key for the KSN counter
+pub fn derive_transaction_key(bdk: &TdesKey, ksn: &Ksn) -> Result<TdesKey> {
+    let ipek = derive_ipek(bdk, ksn)?;
+    let mut key = Zeroizing::new([0; 16]);
+    key.copy_from_slice(&ipek.to_bytes());
+
+    let counter = ksn.counter();
+    let mut current = 0;
+    for bit in (0..21).rev().map(|i| 1 << i) {
+        if counter & bit != 0 {
+            current |= bit;
+            key = non_reversible(&key[..], &ksn.with_counter(current)?.register())?;
+        }
+    }
+    TdesKey::from_bytes(&key[..])
+}
+
+/// applies the variant of key type to the transaction key
+pub fn derive_working_key(transaction_key: &TdesKey, key_type: DukptKeyType) -> Result<TdesKey> {
+    let mut key = Zeroizing::new(transaction_key.to_bytes());
+    if key.len() != 16 {
+        return Err(Error::InvalidKeyLength(key.len() * 8));
+    }
+    key[key_type.variant_byte()] ^= 0xFF;
+    key[key_type.variant_byte() + 8] ^= 0xFF;
+    let key = TdesKey::from_bytes(&key)?;
+
+    match key_type {
+        DukptKeyType::DataRequest | DukptKeyType::DataResponse => {
+            let [left, right, _] = key.components();
+            let left = key.encode(&Block::from_bytes(&left.to_bytes())?)?;
+            let right = key.encode(&Block::from_bytes(&right.to_bytes())?)?;
+            let mut data_key = Zeroizing::new(left.to_bytes().to_vec());
+            data_key.extend(right.to_bytes());
+            TdesKey::from_bytes(&data_key)
+        }
+        _ => Ok(key),
+    }
+}
+
+/// terminal side of DUKPT: keeps future keys instead of the initial key
+pub struct DukptTerminal {
+    ksn: Ksn,
+    /// counter of the next transaction
+    counter: u32,
+    /// key for the counter bit i is stored at index i
+    future_keys: [Option<Zeroizing<[u8; 16]>>; 21],
+}
+
+impl fmt::Debug for DukptTerminal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("DukptTerminal", f)
+    }
+}
+
+impl DukptTerminal {
+    /// loads initial key, counter of ksn is ignored
+    pub fn new(ipek: &TdesKey, ksn: &Ksn) -> Result<Self> {
+        let ipek = Zeroizing::new(ipek.to_bytes());
+        if ipek.len() != 16 {
+            return Err(Error::InvalidKeyLength(ipek.len() * 8));
+        }
+        let ksn = ksn.with_counter(0)?;
+        let mut future_keys: [Option<Zeroizing<[u8; 16]>>; 21] = Default::default();
+        for (i, future_key) in future_keys.iter_mut().enumerate() {
+            *future_key = Some(non_reversible(
+                &ipek,
+                &ksn.with_counter(1 << i)?.register(),
+            )?);
+        }
+        Ok(Self {
+            ksn,
+            counter: 1,
+            future_keys,
+        })
+    }
+
+    /// serial number of the next transaction
+    pub fn ksn(&self) -> Result<Ksn> {
+        self.ksn.with_counter(self.counter)
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.counter > MAX_COUNTER
+    }
+
+    /// returns serial number and key of the next transaction, erasing the key afterwards
+    pub fn next_key(&mut self) -> Result<(Ksn, TdesKey)> {
+        let ksn = self.ksn()?;
+        let position = self.counter.trailing_zeros() as usize;
+        let key = self.future_keys[position]
+            .take()
+            .ok_or(Error::CounterExhausted)?;
+
+        let ones = self.counter.count_ones();
+        if ones < MAX_COUNTER_ONES {
+            for i in 0..position {
+                let register = self.ksn.with_counter(self.counter | 1 << i)?.register();
+                self.future_keys[i] = Some(non_reversible(&key[..], &register)?);
+            }
+            self.counter += 1;
+        } else {
+            self.counter += 1 << position;
+        }
+        Ok((ksn, TdesKey::from_bytes(&key[..])?))
+    }
+}
+
+/// non-reversible key generation process, key should be 16 bytes long
+fn non_reversible(key: &[u8], register: &[u8; 8]) -> Result<Zeroizing<[u8; 16]>> {
+    let mut result = Zeroizing::new([0; 16]);
+    let variant = Zeroizing::new(xor(key, &KEY_VARIANT));
+    // right half of the result is made with the key, left one with its variant
+    for (offset, key) in [(8, key), (0, &variant[..])] {
+        let (left, right) = key.split_at(8);
+        let input = Block::from_bytes(&xor(register, right))?;
+        let output = input.encode(&MainKey::from_bytes(left))?.to_bytes();
+        result[offset..offset + 8].copy_from_slice(&xor(&output, right));
+    }
+    Ok(result)
+}
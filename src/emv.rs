@@ -0,0 +1,131 @@
+use sha1::{Digest, Sha1};
+use zeroize::Zeroizing;
+
+use super::mac::{constant_time_eq, retail_mac_padded, MacPadding};
+use super::modes::xor;
+use super::pin_verification::decimalize;
+use super::traits::{FromHexStr, ToHexString};
+use super::{Block, Error, Result, TdesKey};
+
+/// ICC master key derivation method of EMV Book 2, annex A1.4
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum MasterKeyOption {
+    /// rightmost 16 digits of PAN and PAN sequence number
+    A,
+    /// SHA-1 of PAN and PAN sequence number for PANs longer than 16 digits, A otherwise
+    B,
+}
+
+/// derives ICC master key from issuer master key, PAN sequence number should be
+/// 0..=99 (0 if the card has none)
+pub fn derive_master_key(
+    imk: &TdesKey,
+    pan: &str,
+    psn: u8,
+    option: MasterKeyOption,
+) -> Result<TdesKey> {
+    if pan.is_empty() || !pan.chars().all(|ch| ch.is_ascii_digit()) || psn > 99 {
+        return Err(Error::StringParseError(format!("{pan} {psn}")));
+    }
+    let digits = format!("{pan}{psn:02}");
+    let y = match option {
+        MasterKeyOption::B if pan.len() > 16 => decimalized_digest(&digits)?,
+        _ => format!("{:0>16}", &digits[digits.len().saturating_sub(16)..]),
+    };
+
+    let y = Vec::from_hex_str(&y)?;
+    let left = imk.encode(&Block::from_bytes(&y)?)?;
+    let right = imk.encode(&Block::from_bytes(&xor(&y, &[0xFF; 8]))?)?;
+    let mut key = Zeroizing::new(left.to_bytes().to_vec());
+    key.extend(right.to_bytes());
+    Ok(TdesKey::from_bytes(&key)?.with_odd_parity())
+}
+
+/// EMV common session key derivation from the application transaction counter
+pub fn derive_session_key(master_key: &TdesKey, atc: u16) -> Result<TdesKey> {
+    let [high, low] = atc.to_be_bytes();
+    let left = master_key.encode(&Block::from_bytes(&[high, low, 0xF0, 0, 0, 0, 0, 0])?)?;
+    let right = master_key.encode(&Block::from_bytes(&[high, low, 0x0F, 0, 0, 0, 0, 0])?)?;
+    let mut key = Zeroizing::new(left.to_bytes().to_vec());
+    key.extend(right.to_bytes());
+    TdesKey::from_bytes(&key)
+}
+
+/// application cryptogram over CDOL data, MAC algorithm 3 with padding method 2
+pub fn generate_arqc(session_key: &TdesKey, data: &[u8]) -> Result<[u8; 8]> {
+    retail_mac_padded(session_key, &MacPadding::Method2.pad(data))
+}
+
+pub fn verify_arqc(session_key: &TdesKey, data: &[u8], arqc: &[u8]) -> Result<bool> {
+    Ok(constant_time_eq(&generate_arqc(session_key, data)?, arqc))
+}
+
+/// ARPC method 1: ARQC xored with the authorisation response code, encoded with session key
+pub fn generate_arpc_method_1(
+    session_key: &TdesKey,
+    arqc: &[u8; 8],
+    arc: &[u8; 2],
+) -> Result<[u8; 8]> {
+    let mut arc_block = [0; 8];
+    arc_block[..2].copy_from_slice(arc);
+    let block = Block::from_bytes(&xor(arqc, &arc_block))?;
+    Ok(session_key.encode(&block)?.to_bytes())
+}
+
+pub fn verify_arpc_method_1(
+    session_key: &TdesKey,
+    arqc: &[u8; 8],
+    arc: &[u8; 2],
+    arpc: &[u8],
+) -> Result<bool> {
+    Ok(constant_time_eq(
+        &generate_arpc_method_1(session_key, arqc, arc)?,
+        arpc,
+    ))
+}
+
+/// ARPC method 2: leftmost 4 bytes of MAC over ARQC, card status update and
+/// up to 8 bytes of proprietary authentication data
+pub fn generate_arpc_method_2(
+    session_key: &TdesKey,
+    arqc: &[u8; 8],
+    csu: &[u8; 4],
+    proprietary_data: &[u8],
+) -> Result<[u8; 4]> {
+    if proprietary_data.len() > 8 {
+        return Err(Error::InvalidIterableLength {
+            expected: 64,
+            got: proprietary_data.len() * 8,
+        });
+    }
+    let mut data = arqc.to_vec();
+    data.extend(csu);
+    data.extend(proprietary_data);
+    let mac = retail_mac_padded(session_key, &MacPadding::Method2.pad(&data))?;
+
+    let mut arpc = [0; 4];
+    arpc.copy_from_slice(&mac[..4]);
+    Ok(arpc)
+}
+
+pub fn verify_arpc_method_2(
+    session_key: &TdesKey,
+    arqc: &[u8; 8],
+    csu: &[u8; 4],
+    proprietary_data: &[u8],
+    arpc: &[u8],
+) -> Result<bool> {
+    let expected = generate_arpc_method_2(session_key, arqc, csu, proprietary_data)?;
+    Ok(constant_time_eq(&expected, arpc))
+}
+
+/// 16 digits of SHA-1 over BCD digits: decimal digits of the digest first, then
+/// its hex letters as 0..=5
+fn decimalized_digest(digits: &str) -> Result<String> {
+    let digits = match digits.len() % 2 {
+        0 => digits.to_string(),
+        _ => format!("0{digits}"),
+    };
+    let digest = Sha1::digest(Vec::from_hex_str(&digits)?).to_upper_hex();
+    Ok(decimalize(&digest, 16))
+}
@@ -1,13 +1,51 @@
 use thiserror::Error;
 
+use super::KeyWeakness;
+
 #[derive(Debug, Clone, Error)]
 pub enum Error {
     #[error("invalid round, expected (1 <= round <= 16), got {0}")]
     InvalidRound(u8),
+    #[error("invalid round range, expected (1 <= start <= end <= 16), got {start}..={end}")]
+    InvalidRoundRange { start: u8, end: u8 },
     #[error("couldn't convert string {0} to bitvec (is it hex?)")]
     StringParseError(String),
     #[error(
         "expected iterable to be at least/exactly {expected} bits long, but provided iterable was of length {got}"
     )]
     InvalidIterableLength { expected: usize, got: usize },
+    #[error("key doesn't have odd parity")]
+    InvalidParity,
+    #[error("key is rejected as {0:?}")]
+    WeakKey(KeyWeakness),
+    #[error("invalid key length, expected 64, 128 or 192 bits, got {0}")]
+    InvalidKeyLength(usize),
+    #[error("key check value mismatch, expected {expected}, got {got}")]
+    KcvMismatch { expected: String, got: String },
+    #[error("invalid key block: {0}")]
+    InvalidKeyBlock(String),
+    #[error("MAC verification failed")]
+    MacMismatch,
+    #[error("couldn't get random bytes: {0}")]
+    RandomSource(String),
+    #[error("table {0} contains out of range values")]
+    InvalidTable(String),
+    #[error("CMS key checksum verification failed")]
+    ChecksumMismatch,
+    #[error("DUKPT transaction counter is exhausted")]
+    CounterExhausted,
+    #[error("invalid PIN block: {0}")]
+    InvalidPinBlock(String),
+    #[error("invalid PIN: {0}")]
+    InvalidPin(String),
+    #[error("I/O error: {0}")]
+    Io(String),
+    #[error("mutual authentication failed")]
+    AuthenticationFailed,
+    #[error("invalid APDU: {0}")]
+    InvalidApdu(String),
+    #[error("invalid MAC length, expected 4 to 8 bytes, got {0}")]
+    InvalidMacLength(usize),
+    #[error("padding method 3 needs the total data length up front")]
+    MissingDataLength,
 }
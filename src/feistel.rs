@@ -0,0 +1,81 @@
+use bitvec::prelude::*;
+use std::mem::swap;
+use std::ops::RangeInclusive;
+
+use super::secret::zeroize_bits;
+use super::{Error, Result};
+
+/// balanced Feistel network over 2 * half_width bits.
+/// every round does left ^= round_fn(right, subkeys(round)) and swaps halves,
+/// the swap after the last round is undone, so decoding runs the same rounds backwards
+pub struct Feistel<F, K>
+where
+    F: Fn(&BitVec, &BitVec) -> Result<BitVec>,
+    K: Fn(u8) -> Result<BitVec>,
+{
+    half_width: usize,
+    rounds: RangeInclusive<u8>,
+    round_fn: F,
+    subkeys: K,
+}
+
+impl<F, K> Feistel<F, K>
+where
+    F: Fn(&BitVec, &BitVec) -> Result<BitVec>,
+    K: Fn(u8) -> Result<BitVec>,
+{
+    /// pass 1..=n as rounds for an n-round network. subkeys gets the round number
+    pub fn new(half_width: usize, rounds: RangeInclusive<u8>, round_fn: F, subkeys: K) -> Self {
+        Self {
+            half_width,
+            rounds,
+            round_fn,
+            subkeys,
+        }
+    }
+
+    pub fn half_width(&self) -> usize {
+        self.half_width
+    }
+
+    pub fn rounds(&self) -> &RangeInclusive<u8> {
+        &self.rounds
+    }
+
+    pub fn encode(&self, data: &BitVec) -> Result<BitVec> {
+        self.run(data, self.rounds.clone())
+    }
+
+    pub fn decode(&self, data: &BitVec) -> Result<BitVec> {
+        self.run(data, self.rounds.clone().rev())
+    }
+
+    fn run(&self, data: &BitVec, rounds: impl Iterator<Item = u8>) -> Result<BitVec> {
+        if data.len() != self.half_width * 2 {
+            return Err(Error::InvalidIterableLength {
+                expected: self.half_width * 2,
+                got: data.len(),
+            });
+        }
+        let (left, right) = data.split_at(self.half_width);
+        let mut left = left.to_owned();
+        let mut right = right.to_owned();
+
+        for round in rounds {
+            let mut subkey = (self.subkeys)(round)?;
+            let output = (self.round_fn)(&right, &subkey);
+            zeroize_bits(&mut subkey);
+            let output = output?;
+            if output.len() != self.half_width {
+                return Err(Error::InvalidIterableLength {
+                    expected: self.half_width,
+                    got: output.len(),
+                });
+            }
+            left ^= output;
+            swap(&mut left, &mut right);
+        }
+        right.extend(left);
+        Ok(right)
+    }
+}
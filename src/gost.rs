@@ -0,0 +1,319 @@
+use bitvec::prelude::*;
+use std::fmt;
+use std::ops::RangeInclusive;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::modes::xor;
+use super::secret::{fmt_redacted, zeroize_bits};
+use super::traits::{BlockCipher, FromHexStr};
+use super::{Block, Error, Feistel, Result};
+
+/// added to N1 in gamma mode (mod 2^32)
+const C2: u32 = 0x01010101;
+/// added to N2 in gamma mode (mod 2^32 - 1)
+const C1: u32 = 0x01010104;
+
+/// s-box sets for GOST 28147-89. tables are indexed by nibble, the first one
+/// substitutes the least significant nibble
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum GostSBox {
+    /// id-tc26-gost-28147-param-Z, fixed by GOST R 34.12-2015 (Magma)
+    Tc26Z,
+    /// id-Gost28147-89-CryptoPro-A-ParamSet (RFC 4357)
+    CryptoProA,
+    /// id-Gost28147-89-CryptoPro-B-ParamSet (RFC 4357)
+    CryptoProB,
+    /// id-Gost28147-89-CryptoPro-C-ParamSet (RFC 4357)
+    CryptoProC,
+    /// id-Gost28147-89-CryptoPro-D-ParamSet (RFC 4357)
+    CryptoProD,
+    /// id-GostR3411-94-TestParamSet (RFC 4357)
+    Test,
+    /// every value should be below 16
+    Custom([[u8; 16]; 8]),
+}
+
+impl GostSBox {
+    pub fn as_table(&self) -> &[[u8; 16]; 8] {
+        match self {
+            Self::Tc26Z => &TC26_Z,
+            Self::CryptoProA => &CRYPTO_PRO_A,
+            Self::CryptoProB => &CRYPTO_PRO_B,
+            Self::CryptoProC => &CRYPTO_PRO_C,
+            Self::CryptoProD => &CRYPTO_PRO_D,
+            Self::Test => &TEST,
+            Self::Custom(table) => table,
+        }
+    }
+
+    fn substitute(&self, value: u32) -> u32 {
+        let table = self.as_table();
+        (0..8).fold(0, |acc, i| {
+            let nibble = (value >> (4 * i) & 0xF) as usize;
+            acc | (table[i][nibble] as u32 & 0xF) << (4 * i)
+        })
+    }
+}
+
+/// how keys, blocks and gamma counters of GOST 28147-89 are laid out in bytes
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum GostByteOrder {
+    /// GOST R 34.12-2015 (Magma): the key's first 32 bits are K1, the block's
+    /// first 32 bits are its left half (N2)
+    BigEndian,
+    /// RFC 5830, OpenSSL gost engine: every key word, N1 and N2 are little-endian
+    /// and N1 comes first
+    LittleEndian,
+}
+
+/// GOST 28147-89 (Magma): 64-bit blocks, 256-bit key, 32 rounds.
+/// big-endian as in GOST R 34.12-2015 by default, legacy GOST 28147-89 data
+/// (RFC 5830) is read with [`GostByteOrder::LittleEndian`] and usually
+/// the CryptoPro-A s-box
+#[derive(Clone, PartialEq, PartialOrd)]
+pub struct Gost28147 {
+    /// always big-endian
+    key: BitVec,
+    s_box: GostSBox,
+    byte_order: GostByteOrder,
+}
+
+impl fmt::Debug for Gost28147 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("Gost28147", f)
+    }
+}
+
+impl Zeroize for Gost28147 {
+    fn zeroize(&mut self) {
+        zeroize_bits(&mut self.key);
+    }
+}
+
+impl Drop for Gost28147 {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Gost28147 {}
+
+impl FromHexStr for Gost28147 {
+    /// passed str should be 64 hex chars long
+    fn from_hex_str(s: &str) -> Result<Self> {
+        if s.len() != 64 {
+            return Err(Error::InvalidIterableLength {
+                expected: 64,
+                got: s.len(),
+            });
+        }
+        Self::new(BitVec::from_hex_str(s)?)
+    }
+}
+
+impl BlockCipher for Gost28147 {
+    fn encode_block(&self, block: &Block) -> Result<Block> {
+        self.encode(block)
+    }
+
+    fn decode_block(&self, block: &Block) -> Result<Block> {
+        self.decode(block)
+    }
+}
+
+impl Gost28147 {
+    /// passed bitvec should contain 256 bits, uses the GOST R 34.12-2015 s-box
+    pub fn new(key: BitVec) -> Result<Self> {
+        if key.len() != 256 {
+            return Err(Error::InvalidIterableLength {
+                expected: 256,
+                got: key.len(),
+            });
+        }
+        Ok(Self {
+            key,
+            s_box: GostSBox::Tc26Z,
+            byte_order: GostByteOrder::BigEndian,
+        })
+    }
+
+    /// the key passed to new is read in this order as well
+    pub fn with_byte_order(mut self, byte_order: GostByteOrder) -> Self {
+        if byte_order != self.byte_order {
+            let mut key = self
+                .key
+                .chunks(32)
+                .flat_map(|word| word.rchunks(8).flat_map(|byte| byte.iter().by_vals()))
+                .collect();
+            std::mem::swap(&mut self.key, &mut key);
+            zeroize_bits(&mut key);
+            self.byte_order = byte_order;
+        }
+        self
+    }
+
+    pub fn byte_order(&self) -> &GostByteOrder {
+        &self.byte_order
+    }
+
+    pub fn with_s_box(mut self, s_box: GostSBox) -> Self {
+        self.s_box = s_box;
+        self
+    }
+
+    pub fn s_box(&self) -> &GostSBox {
+        &self.s_box
+    }
+
+    pub fn encode(&self, block: &Block) -> Result<Block> {
+        let data = self
+            .network()
+            .encode(self.swap_byte_order(block)?.as_bitvec())?;
+        self.swap_byte_order(&Block::new(data)?)
+    }
+
+    pub fn decode(&self, block: &Block) -> Result<Block> {
+        let data = self
+            .network()
+            .decode(self.swap_byte_order(block)?.as_bitvec())?;
+        self.swap_byte_order(&Block::new(data)?)
+    }
+
+    /// counter (gamma) mode of GOST 28147-89, decoding is the same operation.
+    /// data may have any length. N1 and N2 of the standard are the halves of
+    /// the encoded IV, laid out in the cipher's byte order
+    pub fn gamma(&self, iv: &Block, data: &[u8]) -> Result<Vec<u8>> {
+        let (mut n1, mut n2) = self.block_to_halves(&self.encode(iv)?)?;
+
+        let mut result = Vec::with_capacity(data.len());
+        for chunk in data.chunks(8) {
+            n1 = n1.wrapping_add(C2);
+            // addition modulo 2^32 - 1
+            let (sum, overflow) = n2.overflowing_add(C1);
+            n2 = sum + overflow as u32;
+
+            let gamma = self.encode(&self.halves_to_block(n1, n2)?)?.to_bytes();
+            result.extend(xor(chunk, &gamma));
+        }
+        Ok(result)
+    }
+
+    /// converts a block between the cipher's byte order and big-endian, both ways
+    fn swap_byte_order(&self, block: &Block) -> Result<Block> {
+        match self.byte_order {
+            GostByteOrder::BigEndian => Ok(block.clone()),
+            GostByteOrder::LittleEndian => {
+                let mut bytes = block.to_bytes();
+                bytes.reverse();
+                Block::from_bytes(&bytes)
+            }
+        }
+    }
+
+    /// N1 and N2
+    fn block_to_halves(&self, block: &Block) -> Result<(u32, u32)> {
+        let value = u64::from_be_bytes(self.swap_byte_order(block)?.to_bytes());
+        Ok((value as u32, (value >> 32) as u32))
+    }
+
+    fn halves_to_block(&self, n1: u32, n2: u32) -> Result<Block> {
+        let value = (n2 as u64) << 32 | n1 as u64;
+        self.swap_byte_order(&Block::from_bytes(&value.to_be_bytes())?)
+    }
+
+    fn network(
+        &self,
+    ) -> Feistel<impl Fn(&BitVec, &BitVec) -> Result<BitVec> + '_, impl Fn(u8) -> Result<BitVec> + '_>
+    {
+        Feistel::new(
+            32,
+            1..=32,
+            |right, subkey| self.f(right, subkey),
+            |round| Ok(self.key[Self::subkey_bits(round)].to_bitvec()),
+        )
+    }
+
+    /// K1..K8 three times, then K8..K1
+    fn subkey_bits(round: u8) -> RangeInclusive<usize> {
+        let index = match round {
+            1..=24 => (round as usize - 1) % 8,
+            _ => 32 - round as usize,
+        };
+        (index * 32)..=(index * 32 + 31)
+    }
+
+    fn f(&self, right: &BitVec, subkey: &BitVec) -> Result<BitVec> {
+        let sum = to_u32(right).wrapping_add(to_u32(subkey));
+        let result = self.s_box.substitute(sum).rotate_left(11);
+        Ok(from_u32(result))
+    }
+}
+
+fn to_u32(bits: &BitSlice) -> u32 {
+    bits.iter().fold(0, |acc, bit| acc << 1 | *bit as u32)
+}
+
+fn from_u32(value: u32) -> BitVec {
+    (0..32).rev().map(|i| value >> i & 1 == 1).collect()
+}
+
+const TC26_Z: [[u8; 16]; 8] = [
+    [12, 4, 6, 2, 10, 5, 11, 9, 14, 8, 13, 7, 0, 3, 15, 1],
+    [6, 8, 2, 3, 9, 10, 5, 12, 1, 14, 4, 7, 11, 13, 0, 15],
+    [11, 3, 5, 8, 2, 15, 10, 13, 14, 1, 7, 4, 12, 9, 6, 0],
+    [12, 8, 2, 1, 13, 4, 15, 6, 7, 0, 10, 5, 3, 14, 9, 11],
+    [7, 15, 5, 10, 8, 1, 6, 13, 0, 9, 3, 14, 11, 4, 2, 12],
+    [5, 13, 15, 6, 9, 2, 12, 10, 11, 7, 8, 1, 4, 3, 14, 0],
+    [8, 14, 2, 5, 6, 9, 1, 12, 15, 4, 11, 0, 13, 10, 3, 7],
+    [1, 7, 14, 13, 0, 5, 8, 3, 4, 15, 10, 6, 9, 12, 11, 2],
+];
+const CRYPTO_PRO_A: [[u8; 16]; 8] = [
+    [9, 6, 3, 2, 8, 11, 1, 7, 10, 4, 14, 15, 12, 0, 13, 5],
+    [3, 7, 14, 9, 8, 10, 15, 0, 5, 2, 6, 12, 11, 4, 13, 1],
+    [14, 4, 6, 2, 11, 3, 13, 8, 12, 15, 5, 10, 0, 7, 1, 9],
+    [14, 7, 10, 12, 13, 1, 3, 9, 0, 2, 11, 4, 15, 8, 5, 6],
+    [11, 5, 1, 9, 8, 13, 15, 0, 14, 4, 2, 3, 12, 7, 10, 6],
+    [3, 10, 13, 12, 1, 2, 0, 11, 7, 5, 9, 4, 8, 15, 14, 6],
+    [1, 13, 2, 9, 7, 10, 6, 0, 8, 12, 4, 5, 15, 3, 11, 14],
+    [11, 10, 15, 5, 0, 12, 14, 8, 6, 2, 3, 9, 1, 7, 13, 4],
+];
+const CRYPTO_PRO_B: [[u8; 16]; 8] = [
+    [8, 4, 11, 1, 3, 5, 0, 9, 2, 14, 10, 12, 13, 6, 7, 15],
+    [0, 1, 2, 10, 4, 13, 5, 12, 9, 7, 3, 15, 11, 8, 6, 14],
+    [14, 12, 0, 10, 9, 2, 13, 11, 7, 5, 8, 15, 3, 6, 1, 4],
+    [7, 5, 0, 13, 11, 6, 1, 2, 3, 10, 12, 15, 4, 14, 9, 8],
+    [2, 7, 12, 15, 9, 5, 10, 11, 1, 4, 0, 13, 6, 8, 14, 3],
+    [8, 3, 2, 6, 4, 13, 14, 11, 12, 1, 7, 15, 10, 0, 9, 5],
+    [5, 2, 10, 11, 9, 1, 12, 3, 7, 4, 13, 0, 6, 15, 8, 14],
+    [0, 4, 11, 14, 8, 3, 7, 1, 10, 2, 9, 6, 15, 13, 5, 12],
+];
+const CRYPTO_PRO_C: [[u8; 16]; 8] = [
+    [1, 11, 12, 2, 9, 13, 0, 15, 4, 5, 8, 14, 10, 7, 6, 3],
+    [0, 1, 7, 13, 11, 4, 5, 2, 8, 14, 15, 12, 9, 10, 6, 3],
+    [8, 2, 5, 0, 4, 9, 15, 10, 3, 7, 12, 13, 6, 14, 1, 11],
+    [3, 6, 0, 1, 5, 13, 10, 8, 11, 2, 9, 7, 14, 15, 12, 4],
+    [8, 13, 11, 0, 4, 5, 1, 2, 9, 3, 12, 14, 6, 15, 10, 7],
+    [12, 9, 11, 1, 8, 14, 2, 4, 7, 3, 6, 5, 10, 0, 15, 13],
+    [10, 9, 6, 8, 13, 14, 2, 0, 15, 3, 5, 11, 4, 1, 12, 7],
+    [7, 4, 0, 5, 10, 2, 15, 14, 12, 6, 1, 11, 13, 9, 3, 8],
+];
+const CRYPTO_PRO_D: [[u8; 16]; 8] = [
+    [15, 12, 2, 10, 6, 4, 5, 0, 7, 9, 14, 13, 1, 11, 8, 3],
+    [11, 6, 3, 4, 12, 15, 14, 2, 7, 13, 8, 0, 5, 10, 9, 1],
+    [1, 12, 11, 0, 15, 14, 6, 5, 10, 13, 4, 8, 9, 3, 7, 2],
+    [1, 5, 14, 12, 10, 7, 0, 13, 6, 2, 11, 4, 9, 3, 15, 8],
+    [0, 12, 8, 9, 13, 2, 10, 11, 7, 3, 6, 5, 4, 14, 15, 1],
+    [8, 0, 15, 3, 2, 5, 14, 11, 1, 10, 4, 7, 12, 9, 13, 6],
+    [3, 0, 6, 15, 1, 14, 9, 2, 13, 8, 12, 4, 11, 10, 5, 7],
+    [1, 10, 6, 8, 15, 11, 0, 4, 12, 3, 5, 9, 7, 13, 2, 14],
+];
+const TEST: [[u8; 16]; 8] = [
+    [4, 10, 9, 2, 13, 8, 0, 14, 6, 11, 1, 12, 7, 15, 5, 3],
+    [14, 11, 4, 12, 6, 13, 15, 10, 2, 3, 8, 1, 0, 7, 5, 9],
+    [5, 8, 1, 13, 10, 3, 4, 2, 14, 15, 12, 7, 6, 0, 9, 11],
+    [7, 13, 10, 1, 0, 8, 9, 15, 14, 4, 6, 12, 11, 2, 5, 3],
+    [6, 12, 7, 1, 5, 15, 13, 8, 4, 10, 9, 14, 0, 3, 11, 2],
+    [4, 11, 10, 0, 7, 2, 1, 13, 3, 6, 8, 5, 9, 12, 15, 14],
+    [13, 11, 4, 1, 3, 15, 5, 9, 0, 10, 14, 7, 6, 8, 2, 12],
+    [1, 15, 13, 0, 5, 7, 10, 4, 9, 2, 3, 14, 6, 11, 8, 12],
+];
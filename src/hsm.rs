@@ -0,0 +1,331 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use zeroize::Zeroizing;
+
+use super::mac::{constant_time_eq, retail_mac, MacPadding};
+use super::modes::ecb_encode;
+use super::pin_block::{translate_pin_block, PinBlockFormat};
+use super::traits::{BlockCipher, FromHexStr, ToHexString};
+use super::{cvv, Block, Error, Result, TdesKey};
+
+/// response fields or error code
+type CommandResult = std::result::Result<Vec<String>, &'static str>;
+
+/// error codes of the responses
+pub const NO_ERROR: &str = "00";
+pub const VERIFICATION_FAILURE: &str = "01";
+pub const INVALID_KEY: &str = "10";
+pub const INVALID_DATA: &str = "15";
+pub const UNKNOWN_COMMAND: &str = "68";
+
+/// xored into the second LMK byte for every half of double and triple length keys, so a
+/// half only decodes in its own position
+const DOUBLE_LENGTH_VARIANTS: [u8; 2] = [0xA6, 0x5A];
+const TRIPLE_LENGTH_VARIANTS: [u8; 3] = [0x6A, 0xDE, 0x2B];
+
+/// type a key is bound to under LMK, codes and LMK variants follow Thales conventions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyType {
+    /// zone master key, encodes keys exported to another zone
+    Zmk,
+    /// zone PIN key, encodes PIN blocks
+    Zpk,
+    /// terminal authentication key, generates and verifies MACs
+    Tak,
+    /// card verification key pair
+    Cvk,
+}
+
+impl KeyType {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Zmk => "000",
+            Self::Zpk => "001",
+            Self::Tak => "003",
+            Self::Cvk => "402",
+        }
+    }
+
+    /// xored into the first LMK byte, so every type is encoded under its own LMK
+    fn variant(&self) -> u8 {
+        match self {
+            Self::Zmk => 0xA6,
+            Self::Zpk => 0x5A,
+            Self::Tak => 0x6A,
+            Self::Cvk => 0xDE,
+        }
+    }
+}
+
+/// payment HSM stand-in keeping keys encoded under the local master key (LMK).
+///
+/// every message is a 2 byte big-endian length followed by ASCII payload: requests are
+/// a 2 char command code and `;` separated fields, responses are the command code with
+/// its last char incremented, a 2 digit error code and response fields. keys are
+/// passed around as their type code followed by every half encoded under the LMK
+/// variant of the type and of the half's position, a key tagged with another type is
+/// answered with `10`.
+///
+/// - `GK` key type code, key length (2 or 3 components) -> key, KCV
+/// - `EK` ZMK, key type code, key -> key under ZMK, KCV
+/// - `TP` source ZPK, destination ZPK, source and destination formats (0..=3), PIN
+///   block, PAN -> PIN block
+/// - `GM` TAK, hex data -> retail MAC over zero padded data
+/// - `VM` TAK, hex data, MAC -> error code only
+/// - `CV` CVK pair, PAN, expiry, service code -> CVV
+pub struct Hsm {
+    lmk: TdesKey,
+}
+
+impl Hsm {
+    pub fn new(lmk: TdesKey) -> Self {
+        Self { lmk }
+    }
+
+    /// serves every connection in its own thread until accepting fails
+    pub fn serve(&self, listener: &TcpListener) -> Result<()> {
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = stream.map_err(|err| Error::Io(err.to_string()))?;
+                scope.spawn(move || self.handle(stream));
+            }
+            Ok(())
+        })
+    }
+
+    /// answers requests of a single connection until it is closed
+    pub fn handle(&self, mut stream: TcpStream) -> Result<()> {
+        while let Some(request) = read_frame(&mut stream)? {
+            write_frame(&mut stream, &self.execute(&request))?;
+        }
+        Ok(())
+    }
+
+    /// processes a single request payload
+    pub fn execute(&self, request: &[u8]) -> Vec<u8> {
+        let request = String::from_utf8_lossy(request);
+        let code = request.get(..2).unwrap_or_default();
+        let fields: Vec<&str> = match request.get(2..) {
+            Some("") | None => Vec::new(),
+            Some(fields) => fields.split(';').collect(),
+        };
+        let result = match code {
+            "GK" => self.generate_key(&fields),
+            "EK" => self.export_key(&fields),
+            "TP" => self.translate_pin(&fields),
+            "GM" => self.generate_mac(&fields),
+            "VM" => self.verify_mac(&fields),
+            "CV" => self.generate_cvv(&fields),
+            _ => Err(UNKNOWN_COMMAND),
+        };
+
+        let mut response = response_code(code);
+        match result {
+            Ok(fields) => {
+                response.push_str(NO_ERROR);
+                response.push_str(&fields.join(";"));
+            }
+            Err(error_code) => response.push_str(error_code),
+        }
+        response.into_bytes()
+    }
+
+    /// encodes key under the LMK variants of its type and tags it with the type code, the
+    /// key should have odd parity
+    pub fn import_key(&self, key_type: KeyType, key: &TdesKey) -> Result<String> {
+        if !key.has_odd_parity() {
+            return Err(Error::InvalidParity);
+        }
+        let encoded = self.run_halves(key_type, &Zeroizing::new(key.to_bytes()), false)?;
+        Ok(format!("{}{}", key_type.code(), encoded.to_upper_hex()))
+    }
+
+    fn generate_key(&self, fields: &[&str]) -> CommandResult {
+        let [key_type, components] = fields else {
+            return Err(INVALID_DATA);
+        };
+        let key_type = parse_key_type(key_type)?;
+        let components = match *components {
+            "2" => 2,
+            "3" => 3,
+            _ => return Err(INVALID_DATA),
+        };
+        let key = TdesKey::generate(components).map_err(|_| INVALID_KEY)?;
+        Ok(vec![
+            self.import_key(key_type, &key).map_err(|_| INVALID_KEY)?,
+            key.kcv().map_err(|_| INVALID_KEY)?,
+        ])
+    }
+
+    fn export_key(&self, fields: &[&str]) -> CommandResult {
+        let [zmk, key_type, key] = fields else {
+            return Err(INVALID_DATA);
+        };
+        let zmk = self.load_key(KeyType::Zmk, zmk)?;
+        let key = self.load_key(parse_key_type(key_type)?, key)?;
+        let exported = ecb_encode(&zmk, &key.to_bytes()).map_err(|_| INVALID_KEY)?;
+        Ok(vec![
+            exported.to_upper_hex(),
+            key.kcv().map_err(|_| INVALID_KEY)?,
+        ])
+    }
+
+    fn translate_pin(&self, fields: &[&str]) -> CommandResult {
+        let [from_key, to_key, from_format, to_format, pin_block, pan] = fields else {
+            return Err(INVALID_DATA);
+        };
+        let from_key = self.load_key(KeyType::Zpk, from_key)?;
+        let to_key = self.load_key(KeyType::Zpk, to_key)?;
+        let pin_block = Vec::from_hex_str(pin_block)
+            .ok()
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+            .ok_or(INVALID_DATA)?;
+        let translated = translate_pin_block(
+            &from_key,
+            pin_block_format(from_format)?,
+            &to_key,
+            pin_block_format(to_format)?,
+            &pin_block,
+            pan,
+        )
+        .map_err(|_| INVALID_DATA)?;
+        Ok(vec![translated.to_upper_hex()])
+    }
+
+    fn generate_mac(&self, fields: &[&str]) -> CommandResult {
+        let [key, data] = fields else {
+            return Err(INVALID_DATA);
+        };
+        Ok(vec![self.mac(key, data)?.to_upper_hex()])
+    }
+
+    fn verify_mac(&self, fields: &[&str]) -> CommandResult {
+        let [key, data, mac] = fields else {
+            return Err(INVALID_DATA);
+        };
+        let mac = Vec::from_hex_str(mac).map_err(|_| INVALID_DATA)?;
+        match constant_time_eq(&self.mac(key, data)?, &mac) {
+            true => Ok(Vec::new()),
+            false => Err(VERIFICATION_FAILURE),
+        }
+    }
+
+    fn generate_cvv(&self, fields: &[&str]) -> CommandResult {
+        let [cvk, pan, expiry, service_code] = fields else {
+            return Err(INVALID_DATA);
+        };
+        let cvk = self.load_key(KeyType::Cvk, cvk)?;
+        let value = cvv::cvv(&cvk, pan, expiry, service_code).map_err(|err| match err {
+            Error::InvalidKeyLength(_) => INVALID_KEY,
+            _ => INVALID_DATA,
+        })?;
+        Ok(vec![value])
+    }
+
+    /// retail MAC over zero padded data with double-length key
+    fn mac(&self, key: &str, data: &str) -> std::result::Result<Vec<u8>, &'static str> {
+        let key = self.load_key(KeyType::Tak, key)?;
+        let data = Vec::from_hex_str(data).map_err(|_| INVALID_DATA)?;
+        retail_mac(&key, &data, MacPadding::Method1, 8).map_err(|_| INVALID_KEY)
+    }
+
+    /// decodes key tagged with the code of the expected type, a retagged key decodes
+    /// under the wrong LMK variants and is rejected by the parity check
+    fn load_key(&self, key_type: KeyType, key: &str) -> std::result::Result<TdesKey, &'static str> {
+        let encoded = key.strip_prefix(key_type.code()).ok_or(INVALID_KEY)?;
+        let encoded = Vec::from_hex_str(encoded).map_err(|_| INVALID_KEY)?;
+        let key = Zeroizing::new(
+            self.run_halves(key_type, &encoded, true)
+                .map_err(|_| INVALID_KEY)?,
+        );
+        let key = TdesKey::from_bytes(&key).map_err(|_| INVALID_KEY)?;
+        match key.has_odd_parity() {
+            true => Ok(key),
+            false => Err(INVALID_KEY),
+        }
+    }
+
+    /// encodes or decodes every 8 byte half of key under the LMK variant of the type
+    /// and of the half's position
+    fn run_halves(&self, key_type: KeyType, key: &[u8], decode: bool) -> Result<Vec<u8>> {
+        let variants = match key.len() {
+            16 => DOUBLE_LENGTH_VARIANTS.as_slice(),
+            24 => TRIPLE_LENGTH_VARIANTS.as_slice(),
+            len => return Err(Error::InvalidKeyLength(len * 8)),
+        };
+        let mut result = Vec::with_capacity(key.len());
+        for (half, variant) in key.chunks(8).zip(variants) {
+            let lmk = self.lmk_variant(key_type, *variant)?;
+            let half = Block::from_bytes(half)?;
+            let half = match decode {
+                false => lmk.encode_block(&half)?,
+                true => lmk.decode_block(&half)?,
+            };
+            result.extend(half.to_bytes());
+        }
+        Ok(result)
+    }
+
+    fn lmk_variant(&self, key_type: KeyType, half_variant: u8) -> Result<TdesKey> {
+        let mut lmk = Zeroizing::new(self.lmk.to_bytes());
+        lmk[0] ^= key_type.variant();
+        lmk[1] ^= half_variant;
+        TdesKey::from_bytes(&lmk)
+    }
+}
+
+/// reads length-prefixed message, returns None if the stream is closed
+pub fn read_frame(stream: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut len = [0; 2];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(Error::Io(err.to_string())),
+    }
+    let mut payload = vec![0; u16::from_be_bytes(len) as usize];
+    stream
+        .read_exact(&mut payload)
+        .map_err(|err| Error::Io(err.to_string()))?;
+    Ok(Some(payload))
+}
+
+/// writes length-prefixed message
+pub fn write_frame(stream: &mut impl Write, payload: &[u8]) -> Result<()> {
+    let len = u16::try_from(payload.len()).map_err(|_| Error::InvalidIterableLength {
+        expected: u16::MAX as usize,
+        got: payload.len(),
+    })?;
+    stream
+        .write_all(&len.to_be_bytes())
+        .and_then(|()| stream.write_all(payload))
+        .map_err(|err| Error::Io(err.to_string()))
+}
+
+/// command code with its last char incremented, e.g. GK -> GL
+fn response_code(code: &str) -> String {
+    match code.as_bytes() {
+        [first, last] if first.is_ascii_uppercase() && last.is_ascii_uppercase() => {
+            format!("{}{}", *first as char, (last + 1) as char)
+        }
+        _ => "ZZ".to_string(),
+    }
+}
+
+fn parse_key_type(code: &str) -> std::result::Result<KeyType, &'static str> {
+    [KeyType::Zmk, KeyType::Zpk, KeyType::Tak, KeyType::Cvk]
+        .into_iter()
+        .find(|key_type| key_type.code() == code)
+        .ok_or(INVALID_DATA)
+}
+
+fn pin_block_format(format: &str) -> std::result::Result<PinBlockFormat, &'static str> {
+    match format {
+        "0" => Ok(PinBlockFormat::Iso0),
+        "1" => Ok(PinBlockFormat::Iso1),
+        "2" => Ok(PinBlockFormat::Iso2),
+        "3" => Ok(PinBlockFormat::Iso3),
+        _ => Err(INVALID_DATA),
+    }
+}
@@ -0,0 +1,41 @@
+use super::mac::{cmac, constant_time_eq};
+use super::modes::encode_bytes;
+use super::{BlockCipher, Error, Result};
+
+/// how the key check value is computed
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub enum KcvMethod {
+    /// the key encodes a zero block
+    #[default]
+    Legacy,
+    /// CMAC of a zero block (ANSI X9.24-1:2017)
+    Cmac,
+}
+
+/// returns leftmost `digits` hex digits (uppercase) of the check value, digits should be 1..=16
+pub fn kcv(cipher: &impl BlockCipher, method: KcvMethod, digits: usize) -> Result<String> {
+    if !(1..=16).contains(&digits) {
+        return Err(Error::InvalidIterableLength {
+            expected: 16,
+            got: digits,
+        });
+    }
+    let value = match method {
+        KcvMethod::Legacy => encode_bytes(cipher, &[0; 8])?,
+        KcvMethod::Cmac => cmac(cipher, &[0; 8])?,
+    };
+    let hex = value
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<String>();
+    Ok(hex[..digits].to_string())
+}
+
+/// compares expected check value (any case, 1..=16 digits) in constant time
+pub fn verify_kcv(cipher: &impl BlockCipher, method: KcvMethod, expected: &str) -> Result<bool> {
+    let actual = kcv(cipher, method, expected.len())?;
+    Ok(constant_time_eq(
+        actual.as_bytes(),
+        expected.to_uppercase().as_bytes(),
+    ))
+}
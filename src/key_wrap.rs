@@ -0,0 +1,73 @@
+use rand_core::{CryptoRng, OsRng, RngCore};
+use sha1::{Digest, Sha1};
+use zeroize::Zeroizing;
+
+use super::mac::constant_time_eq;
+use super::modes::{cbc_decode, cbc_encode};
+use super::{Block, Error, Result, TdesKey};
+
+/// IV of the second encryption pass
+const WRAP_IV: [u8; 8] = [0x4a, 0xdd, 0xa2, 0x2c, 0x79, 0xe8, 0x21, 0x05];
+
+/// wraps content-encryption key under key-encryption key as in RFC 3217, IV is
+/// taken from OS randomness
+pub fn wrap_key(kek: &TdesKey, cek: &TdesKey) -> Result<Vec<u8>> {
+    wrap_key_with(kek, cek, &mut OsRng)
+}
+
+/// same as wrap_key, but takes IV from rng
+pub fn wrap_key_with<R: RngCore + CryptoRng>(
+    kek: &TdesKey,
+    cek: &TdesKey,
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    let mut iv = [0; 8];
+    rng.try_fill_bytes(&mut iv)
+        .map_err(|err| Error::RandomSource(err.to_string()))?;
+
+    // double-length keys are wrapped as three-key ones with K3 = K1
+    let mut cek_icv = Zeroizing::new(
+        cek.components()
+            .iter()
+            .flat_map(|key| key.with_odd_parity().to_bytes())
+            .collect::<Vec<u8>>(),
+    );
+    let icv = checksum(&cek_icv);
+    cek_icv.extend(icv);
+
+    let mut temp = iv.to_vec();
+    temp.extend(cbc_encode(kek, &Block::from_bytes(&iv)?, &cek_icv)?);
+    temp.reverse();
+    cbc_encode(kek, &Block::from_bytes(&WRAP_IV)?, &temp)
+}
+
+/// unwraps content-encryption key, checking its checksum and parity
+pub fn unwrap_key(kek: &TdesKey, wrapped: &[u8]) -> Result<TdesKey> {
+    if wrapped.len() != 40 {
+        return Err(Error::InvalidIterableLength {
+            expected: 40 * 8,
+            got: wrapped.len() * 8,
+        });
+    }
+    let mut temp = cbc_decode(kek, &Block::from_bytes(&WRAP_IV)?, wrapped)?;
+    temp.reverse();
+    let (iv, encrypted) = temp.split_at(8);
+    let cek_icv = Zeroizing::new(cbc_decode(kek, &Block::from_bytes(iv)?, encrypted)?);
+    let (cek, icv) = cek_icv.split_at(24);
+    if !constant_time_eq(&checksum(cek), icv) {
+        return Err(Error::ChecksumMismatch);
+    }
+
+    let cek = TdesKey::from_bytes(cek)?;
+    if !cek.has_odd_parity() {
+        return Err(Error::InvalidParity);
+    }
+    Ok(cek)
+}
+
+/// CMS key checksum, first 8 bytes of SHA-1 digest
+fn checksum(key: &[u8]) -> [u8; 8] {
+    let mut icv = [0; 8];
+    icv.copy_from_slice(&Sha1::digest(key)[..8]);
+    icv
+}
@@ -1,13 +1,48 @@
+pub mod bac;
 pub mod block;
+pub mod components;
+pub mod cvv;
+pub mod dukpt;
+pub mod emv;
 pub mod error;
+pub mod feistel;
+pub mod gost;
+pub mod hsm;
+pub mod kcv;
+pub mod key_wrap;
+pub mod mac;
 pub mod main_key;
+pub mod mifare;
+pub mod modes;
+pub mod pin_block;
+pub mod pin_verification;
+pub mod scp;
+pub mod sdes;
+pub mod secret;
 pub mod shift;
+pub mod subkey;
+pub mod tables;
+pub mod tdes;
+pub mod tr31;
 pub mod traits;
+pub mod weakness;
 
 pub use block::Block;
+pub use components::KeyComponent;
 pub use error::Error;
+pub use feistel::Feistel;
+pub use gost::{Gost28147, GostByteOrder, GostSBox};
+pub use kcv::KcvMethod;
 pub use main_key::MainKey;
-pub use shift::{ShiftDirection, ShiftSchemes};
-pub use traits::{FromHexStr, ToHexString};
+pub use sdes::SDes;
+pub use secret::SecretKey;
+pub use shift::{permute, ShiftDirection, ShiftSchemes};
+pub use subkey::Subkey;
+pub use tables::DesTables;
+pub use tdes::TdesKey;
+pub use traits::{BlockCipher, FromHexStr, ToHexString};
+pub use weakness::{KeyWeakness, WeakKeyPolicy};
+
+pub use rand_core;
 
 pub type Result<T> = std::result::Result<T, Error>;
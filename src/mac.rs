@@ -0,0 +1,264 @@
+use std::fmt;
+
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use super::modes::{check_blocks, encode_bytes, xor};
+use super::secret::fmt_redacted;
+use super::{Block, BlockCipher, Error, MainKey, Result, TdesKey};
+
+/// reduction constant for doubling 64-bit blocks in GF(2^64)
+const RB: u8 = 0x1B;
+
+/// ISO 9797-1 padding methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MacPadding {
+    /// zeros up to a whole block, empty data is a zero block
+    Method1,
+    /// 0x80 and zeros up to a whole block
+    Method2,
+    /// bit length of the data as a leading block, then zeros up to a whole block
+    Method3,
+}
+
+impl MacPadding {
+    pub fn pad(self, data: &[u8]) -> Vec<u8> {
+        let mut padded = match self {
+            MacPadding::Method1 | MacPadding::Method2 => data.to_vec(),
+            MacPadding::Method3 => {
+                let mut padded = ((data.len() as u64) * 8).to_be_bytes().to_vec();
+                padded.extend(data);
+                padded
+            }
+        };
+        if self == MacPadding::Method2 {
+            padded.push(0x80);
+        }
+        padded.resize(padded.len().next_multiple_of(8).max(8), 0);
+        padded
+    }
+}
+
+/// ISO 9797-1 MAC algorithm 1 (ANSI X9.9) truncated to the leftmost `length` bytes
+pub fn iso9797_mac1(
+    key: &MainKey,
+    data: &[u8],
+    padding: MacPadding,
+    length: usize,
+) -> Result<Vec<u8>> {
+    check_mac_length(length)?;
+    Ok(cbc_mac(key, &padding.pad(data))?[..length].to_vec())
+}
+
+/// truncation length is taken from the passed MAC
+pub fn verify_iso9797_mac1(
+    key: &MainKey,
+    data: &[u8],
+    padding: MacPadding,
+    mac: &[u8],
+) -> Result<bool> {
+    let expected = iso9797_mac1(key, data, padding, mac.len())?;
+    Ok(constant_time_eq(&expected, mac))
+}
+
+/// CMAC (NIST SP 800-38B) over 64-bit block ciphers
+pub fn cmac(cipher: &impl BlockCipher, data: &[u8]) -> Result<[u8; 8]> {
+    let l = encode_bytes(cipher, &[0; 8])?;
+    let k1 = double(&l);
+    let k2 = double(&k1);
+
+    let complete = !data.is_empty() && data.len().is_multiple_of(8);
+    let last_start = match complete {
+        true => data.len() - 8,
+        false => data.len() - data.len() % 8,
+    };
+    let (head, tail) = data.split_at(last_start);
+
+    let mut last = [0; 8];
+    last[..tail.len()].copy_from_slice(tail);
+    let last = match complete {
+        true => xor(&last, &k1),
+        false => {
+            last[tail.len()] = 0x80;
+            xor(&last, &k2)
+        }
+    };
+
+    let state = cbc_mac(cipher, head)?;
+    encode_bytes(cipher, &xor(&state, &last))
+}
+
+/// plain CBC-MAC without padding, data length should be a multiple of 8
+pub(crate) fn cbc_mac(cipher: &impl BlockCipher, data: &[u8]) -> Result<[u8; 8]> {
+    chain(cipher, [0; 8], data)
+}
+
+/// ISO 9797-1 MAC algorithm 3 (ANSI X9.19 retail MAC) with a double-length key,
+/// truncated to the leftmost `length` bytes
+pub fn retail_mac(
+    key: &TdesKey,
+    data: &[u8],
+    padding: MacPadding,
+    length: usize,
+) -> Result<Vec<u8>> {
+    check_mac_length(length)?;
+    Ok(retail_mac_padded(key, &padding.pad(data))?[..length].to_vec())
+}
+
+/// truncation length is taken from the passed MAC
+pub fn verify_retail_mac(
+    key: &TdesKey,
+    data: &[u8],
+    padding: MacPadding,
+    mac: &[u8],
+) -> Result<bool> {
+    let expected = retail_mac(key, data, padding, mac.len())?;
+    Ok(constant_time_eq(&expected, mac))
+}
+
+/// retail MAC over already padded data, length should be a multiple of 8
+pub(crate) fn retail_mac_padded(key: &TdesKey, padded: &[u8]) -> Result<[u8; 8]> {
+    let [k1, k2] = double_length(key)?;
+    output_transformation(k1, k2, cbc_mac(k1, padded)?)
+}
+
+/// incremental retail MAC, whole blocks are chained as soon as they arrive
+#[derive(Clone)]
+pub struct RetailMac {
+    k1: MainKey,
+    k2: MainKey,
+    padding: MacPadding,
+    length: usize,
+    data_length: Option<usize>,
+    state: [u8; 8],
+    pending: Zeroizing<Vec<u8>>,
+    received: usize,
+    chained: bool,
+}
+
+impl fmt::Debug for RetailMac {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("RetailMac", f)
+    }
+}
+
+impl RetailMac {
+    /// `data_length` is the total length of the data in bytes. padding method 3 starts
+    /// with it, so there it's required, other methods only check it in `finalize`
+    pub fn new(
+        key: &TdesKey,
+        padding: MacPadding,
+        length: usize,
+        data_length: Option<usize>,
+    ) -> Result<Self> {
+        check_mac_length(length)?;
+        let [k1, k2] = double_length(key)?;
+        let mut mac = Self {
+            k1: k1.clone(),
+            k2: k2.clone(),
+            padding,
+            length,
+            data_length,
+            state: [0; 8],
+            pending: Zeroizing::new(Vec::new()),
+            received: 0,
+            chained: false,
+        };
+        if padding == MacPadding::Method3 {
+            let data_length = data_length.ok_or(Error::MissingDataLength)?;
+            mac.state = cbc_mac(&mac.k1, &((data_length as u64) * 8).to_be_bytes())?;
+            mac.chained = true;
+        }
+        Ok(mac)
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> Result<()> {
+        self.received += data.len();
+        if let Some(data_length) = self.data_length {
+            if self.received > data_length {
+                return Err(length_mismatch(data_length, self.received));
+            }
+        }
+        self.pending.extend(data);
+        let whole = self.pending.len() - self.pending.len() % 8;
+        if whole > 0 {
+            self.state = chain(&self.k1, self.state, &self.pending[..whole])?;
+            self.pending.drain(..whole);
+            self.chained = true;
+        }
+        Ok(())
+    }
+
+    pub fn finalize(self) -> Result<Vec<u8>> {
+        if let Some(data_length) = self.data_length {
+            if self.received != data_length {
+                return Err(length_mismatch(data_length, self.received));
+            }
+        }
+        let tail = match self.padding {
+            MacPadding::Method2 => MacPadding::Method2.pad(&self.pending),
+            // method 1 and the data part of method 3 are zero padded, aligned data is
+            // left as is, while empty data of method 1 is a zero block
+            _ if self.chained && self.pending.is_empty() => Vec::new(),
+            _ => MacPadding::Method1.pad(&self.pending),
+        };
+        let state = chain(&self.k1, self.state, &tail)?;
+        let mac = output_transformation(&self.k1, &self.k2, state)?;
+        Ok(mac[..self.length].to_vec())
+    }
+
+    /// compares in constant time, MAC of another length doesn't match
+    pub fn verify(self, mac: &[u8]) -> Result<bool> {
+        Ok(constant_time_eq(&self.finalize()?, mac))
+    }
+}
+
+/// compares without leaking the position of the first difference
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// CBC chain over whole blocks starting from the passed state
+fn chain(cipher: &impl BlockCipher, mut state: [u8; 8], data: &[u8]) -> Result<[u8; 8]> {
+    check_blocks(data)?;
+    for chunk in data.chunks(8) {
+        state = encode_bytes(cipher, &xor(&state, chunk))?;
+    }
+    Ok(state)
+}
+
+/// the last step of MAC algorithm 3: decoding with K2 and encoding with K1
+fn output_transformation(k1: &MainKey, k2: &MainKey, state: [u8; 8]) -> Result<[u8; 8]> {
+    let state = Block::from_bytes(&state)?;
+    Ok(state.decode(k2)?.encode(k1)?.to_bytes())
+}
+
+/// lengths are reported in bits
+fn length_mismatch(expected: usize, got: usize) -> Error {
+    Error::InvalidIterableLength {
+        expected: expected * 8,
+        got: got * 8,
+    }
+}
+
+fn double_length(key: &TdesKey) -> Result<[&MainKey; 2]> {
+    if key.component_count() != 2 {
+        return Err(Error::InvalidKeyLength(key.component_count() * 64));
+    }
+    let [k1, k2, _] = key.components();
+    Ok([k1, k2])
+}
+
+fn check_mac_length(length: usize) -> Result<()> {
+    if !(4..=8).contains(&length) {
+        return Err(Error::InvalidMacLength(length));
+    }
+    Ok(())
+}
+
+/// multiplies by x in GF(2^64)
+fn double(block: &[u8; 8]) -> [u8; 8] {
+    let value = u64::from_be_bytes(*block);
+    let carry = value >> 63;
+    ((value << 1) ^ (carry * RB as u64)).to_be_bytes()
+}
@@ -1,14 +1,42 @@
-use super::traits::{FromHexStr, ToHexString};
-use super::{Error, Result, ShiftDirection, ShiftSchemes};
+use super::components::{combine, split, KeyComponent};
+use super::kcv::{kcv, verify_kcv, KcvMethod};
+use super::secret::{fmt_redacted, permute_bits, zeroize_bits};
+use super::traits::{BlockCipher, FromHexStr, ToHexString};
+use super::{
+    Block, DesTables, Error, KeyWeakness, Result, ShiftDirection, ShiftSchemes, Subkey,
+    WeakKeyPolicy,
+};
 use bitvec::prelude::*;
+use rand_core::{CryptoRng, OsRng, RngCore};
 use std::fmt;
 use std::str::FromStr;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Clone, PartialEq, PartialOrd)]
 pub struct MainKey {
     key: BitVec,
 }
 
+impl fmt::Debug for MainKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("MainKey", f)
+    }
+}
+
+impl Zeroize for MainKey {
+    fn zeroize(&mut self) {
+        zeroize_bits(&mut self.key);
+    }
+}
+
+impl Drop for MainKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for MainKey {}
+
 impl fmt::Display for MainKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let formatted = self
@@ -47,7 +75,7 @@ impl ToHexString for MainKey {
     /// if you want to have unpadded hex string, consider converting into bitvec and calling this method on the result
     fn to_upper_hex(&self) -> String {
         let converted = self.key.to_upper_hex();
-        if converted.len() % 16 == 0 {
+        if converted.len().is_multiple_of(16) {
             return converted;
         }
         format!(
@@ -60,7 +88,7 @@ impl ToHexString for MainKey {
     /// if you want to have unpadded hex string, consider converting into bitvec and calling this method on the result
     fn to_lower_hex(&self) -> String {
         let converted = self.key.to_lower_hex();
-        if converted.len() % 16 == 0 {
+        if converted.len().is_multiple_of(16) {
             return converted;
         }
         format!(
@@ -71,24 +99,192 @@ impl ToHexString for MainKey {
     }
 }
 
+impl BlockCipher for MainKey {
+    fn encode_block(&self, block: &Block) -> Result<Block> {
+        block.encode(self)
+    }
+
+    fn decode_block(&self, block: &Block) -> Result<Block> {
+        block.decode(self)
+    }
+}
+
 impl MainKey {
     pub fn new(key: BitVec) -> Self {
         Self { key }
     }
 
+    /// bits are taken most significant first
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let key = bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1))
+            .collect();
+        Self::new(key)
+    }
+
+    /// trailing bits not filling a whole byte are dropped
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.key
+            .chunks_exact(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| acc << 1 | *bit as u8))
+            .collect()
+    }
+
+    /// generates 64-bit key with odd parity from OS randomness, weak and semi-weak keys are skipped
+    pub fn generate() -> Result<Self> {
+        Self::generate_with(&mut OsRng, WeakKeyPolicy::default())
+    }
+
+    /// generates 64-bit key with odd parity, keys rejected by the policy are skipped
+    pub fn generate_with<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        policy: WeakKeyPolicy,
+    ) -> Result<Self> {
+        loop {
+            let mut bytes = [0; 8];
+            rng.try_fill_bytes(&mut bytes)
+                .map_err(|err| Error::RandomSource(err.to_string()))?;
+            let key = Self::from_bytes(&bytes).with_odd_parity();
+            if key.check_weakness(policy).is_ok() {
+                return Ok(key);
+            }
+        }
+    }
+
+    /// 6 hex digit check value: the key encoding a zero block
+    pub fn kcv(&self) -> Result<String> {
+        kcv(self, KcvMethod::Legacy, 6)
+    }
+
+    /// check value truncated to `digits` hex digits (1..=16)
+    pub fn kcv_with(&self, method: KcvMethod, digits: usize) -> Result<String> {
+        kcv(self, method, digits)
+    }
+
+    /// compares the check value with expected one in constant time, length of expected sets digits
+    pub fn verify_kcv(&self, method: KcvMethod, expected: &str) -> Result<bool> {
+        verify_kcv(self, method, expected)
+    }
+
+    /// splits key with odd parity into `count` random components using OS randomness
+    pub fn split_components(&self, count: usize) -> Result<Vec<KeyComponent<Self>>> {
+        split(self, count, &mut OsRng)
+    }
+
+    /// same as split_components, but takes randomness from rng
+    pub fn split_components_with<R: RngCore + CryptoRng>(
+        &self,
+        count: usize,
+        rng: &mut R,
+    ) -> Result<Vec<KeyComponent<Self>>> {
+        split(self, count, rng)
+    }
+
+    /// XORs components and checks the result against expected check value
+    pub fn combine_components(
+        components: &[KeyComponent<Self>],
+        expected_kcv: &str,
+    ) -> Result<Self> {
+        combine(components, expected_kcv)
+    }
+
+    /// passed bitvec should contain 64 bits with odd parity in every byte
+    pub fn new_strict(key: BitVec) -> Result<Self> {
+        if key.len() != 64 {
+            return Err(Error::InvalidIterableLength {
+                expected: 64,
+                got: key.len(),
+            });
+        }
+        let key = Self::new(key);
+        if !key.has_odd_parity() {
+            return Err(Error::InvalidParity);
+        }
+        Ok(key)
+    }
+
     pub fn as_bitvec(&self) -> &BitVec {
         &self.key
     }
 
-    /// returns inner BitVec consuming Self
-    pub fn into_bitvec(self) -> BitVec {
-        let result = self.key.to_owned();
-        drop(self);
-        result
+    /// true if key consists of whole bytes each having an odd number of set bits
+    pub fn has_odd_parity(&self) -> bool {
+        self.key.len().is_multiple_of(8)
+            && self.key.chunks(8).all(|byte| byte.count_ones() % 2 == 1)
+    }
+
+    /// returns new key with the lowest bit of every byte set for odd parity
+    pub fn with_odd_parity(&self) -> Self {
+        let mut key = self.key.clone();
+        for byte in key.chunks_exact_mut(8) {
+            let ones = byte[..7].count_ones();
+            byte.set(7, ones % 2 == 0);
+        }
+        Self::new(key)
+    }
+
+    /// returns inner BitVec consuming Self, the caller becomes responsible for wiping it
+    pub fn into_bitvec(mut self) -> BitVec {
+        std::mem::take(&mut self.key)
+    }
+
+    /// classifies 64-bit keys by FIPS 74, parity bits are ignored
+    pub fn weakness(&self) -> Option<KeyWeakness> {
+        if self.key.len() != 64 {
+            return None;
+        }
+        let mut halves = permute_bits(ShiftSchemes::PC1.as_slice(), &self.key).ok()?;
+        let (left, right) = halves.split_at(28);
+        // the key schedule only rotates halves, so a half repeating with a short
+        // period yields the same few round keys over and over
+        let period = |half: &BitSlice| {
+            [1, 2, 4].into_iter().find(|period| {
+                let mut rotated = half.to_bitvec();
+                rotated.rotate_left(*period);
+                let repeats = rotated == half;
+                zeroize_bits(&mut rotated);
+                repeats
+            })
+        };
+        let weakness = match (period(left), period(right)) {
+            (Some(1), Some(1)) => Some(KeyWeakness::Weak),
+            (Some(1..=2), Some(1..=2)) => Some(KeyWeakness::SemiWeak {
+                dual: Self::from_halves(left, right),
+            }),
+            (Some(_), Some(_)) => Some(KeyWeakness::PossiblyWeak),
+            _ => None,
+        };
+        zeroize_bits(&mut halves);
+        weakness
     }
 
-    fn shift_scheme(&mut self, scheme: ShiftSchemes) -> Result<()> {
-        let needed_len = scheme.as_slice().len();
+    /// returns an error if the key has a weakness rejected by the policy
+    pub fn check_weakness(&self, policy: WeakKeyPolicy) -> Result<()> {
+        match self.weakness() {
+            Some(weakness) if policy.rejects(&weakness) => Err(Error::WeakKey(weakness)),
+            _ => Ok(()),
+        }
+    }
+
+    /// builds the key whose PC1 halves are the passed ones rotated by one bit
+    fn from_halves(left: &BitSlice, right: &BitSlice) -> Self {
+        let mut halves: BitVec = BitVec::with_capacity(56);
+        halves.extend_from_bitslice(left);
+        halves[..28].rotate_left(1);
+        halves.extend_from_bitslice(right);
+        halves[28..].rotate_left(1);
+
+        let mut key = bitvec![0; 64];
+        for (bit, pos) in halves.iter().zip(ShiftSchemes::PC1.as_slice()) {
+            key.set(*pos, *bit);
+        }
+        zeroize_bits(&mut halves);
+        Self::new(key).with_odd_parity()
+    }
+
+    fn shift_scheme(&mut self, scheme: &[usize]) -> Result<()> {
+        let needed_len = scheme.len();
         if self.key.len() < needed_len {
             return Err(Error::InvalidIterableLength {
                 expected: needed_len,
@@ -96,33 +292,70 @@ impl MainKey {
             });
         }
 
-        self.key = scheme.shift(self.key.clone())?;
+        let shifted = permute_bits(scheme, &self.key)?;
+        self.replace_key(shifted);
         Ok(())
     }
 
-    /// returns new round shifted key (doesn't mutate self). u8 should be 1..=16
-    fn shift_round(&mut self, round: u8, direction: ShiftDirection) -> Result<()> {
-        let round_shift = direction.get_round_shift(round)? as usize;
-        let (left, right) = self.key.split_at(self.key.len() / 2);
+    /// rotates both key halves by the round's total shift
+    fn shift_round(&mut self, round_shift: usize) -> Result<()> {
+        let half_len = self.key.len() / 2;
+        let (left, right) = self.key.split_at(half_len);
         let mut left = left.to_owned();
         let mut right = right.to_owned();
 
-        left.rotate_left(round_shift);
-        right.rotate_left(round_shift);
-        left.extend(right);
+        left.rotate_left(round_shift % half_len);
+        right.rotate_left(round_shift % half_len);
+        left.extend(right.iter().by_vals());
 
-        self.key = left;
+        zeroize_bits(&mut right);
+        self.replace_key(left);
         Ok(())
     }
 
+    /// wipes the old key before dropping it
+    fn replace_key(&mut self, key: BitVec) {
+        zeroize_bits(&mut self.key);
+        self.key = key;
+    }
+
     /// returns new instance of MainKey
     pub fn get_round_key(&self, round: u8, direction: ShiftDirection) -> Result<Self> {
         let mut new_key = self.clone();
-        new_key.shift_scheme(ShiftSchemes::PC1)?;
-        new_key.shift_round(round, direction)?;
-        new_key.shift_scheme(ShiftSchemes::PC2)?;
+        new_key.shift_scheme(ShiftSchemes::PC1.as_slice())?;
+        new_key.shift_round(direction.get_round_shift(round)? as usize)?;
+        new_key.shift_scheme(ShiftSchemes::PC2.as_slice())?;
+        Ok(new_key)
+    }
+
+    /// same as get_round_key, but uses PC1, PC2 and rotations from the passed tables
+    pub fn get_round_key_with_tables(
+        &self,
+        round: u8,
+        direction: ShiftDirection,
+        tables: &DesTables,
+    ) -> Result<Self> {
+        let mut new_key = self.clone();
+        new_key.shift_scheme(&tables.pc1)?;
+        new_key.shift_round(tables.get_round_shift(round, direction)?)?;
+        new_key.shift_scheme(&tables.pc2)?;
         Ok(new_key)
     }
+
+    /// returns the 16 round keys produced by the FIPS 46 key schedule
+    pub fn get_subkeys(&self) -> Result<[Subkey; 16]> {
+        let mut subkeys = Vec::with_capacity(16);
+        for round in 1..=16 {
+            let round_key = self.get_round_key(round, ShiftDirection::Left)?;
+            subkeys.push(Subkey::new(round_key.into_bitvec())?);
+        }
+        subkeys
+            .try_into()
+            .map_err(|subkeys: Vec<Subkey>| Error::InvalidIterableLength {
+                expected: 16,
+                got: subkeys.len(),
+            })
+    }
 }
 
 #[cfg(test)]
@@ -132,7 +365,7 @@ mod tests {
     #[test]
     fn test_pc1_shift() -> Result<()> {
         let mut key = MainKey::from_hex_str("AABB09182736CCDD")?;
-        key.shift_scheme(ShiftSchemes::PC1)?;
+        key.shift_scheme(ShiftSchemes::PC1.as_slice())?;
         assert_eq!(key, MainKey::from_hex_str("C3C033A33F0CFA")?);
         Ok(())
     }
@@ -0,0 +1,297 @@
+use rand_core::{CryptoRng, OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::mac::constant_time_eq;
+use super::modes::{cbc_decode, cbc_encode, check_blocks, encode_bytes, xor};
+use super::{Block, BlockCipher, Error, Result, TdesKey};
+
+/// DESFire native send mode: every block is xored with the previous output and
+/// decoded, so the card can always encode
+pub fn send_mode_cbc(key: &impl BlockCipher, data: &[u8]) -> Result<Vec<u8>> {
+    check_blocks(data)?;
+    let mut result = Vec::with_capacity(data.len());
+    let mut previous = [0; 8];
+    for chunk in data.chunks(8) {
+        let block = Block::from_bytes(&xor(chunk, &previous))?;
+        previous = key.decode_block(&block)?.to_bytes();
+        result.extend(previous);
+    }
+    Ok(result)
+}
+
+/// DESFire native receive mode of the reader: every block is decoded and xored with
+/// the previous input, so it reads what the card encodes in CBC mode with zero IV
+pub fn receive_mode_cbc(key: &impl BlockCipher, data: &[u8]) -> Result<Vec<u8>> {
+    cbc_decode(key, &Block::from_bytes(&[0; 8])?, data)
+}
+
+/// card side inverse of send mode: every block is encoded and xored with the
+/// previous input
+fn undo_send_mode(key: &impl BlockCipher, data: &[u8]) -> Result<Vec<u8>> {
+    check_blocks(data)?;
+    let mut result = Vec::with_capacity(data.len());
+    let mut previous: &[u8] = &[0; 8];
+    for chunk in data.chunks(8) {
+        result.extend(xor(&encode_bytes(key, chunk)?, previous));
+        previous = chunk;
+    }
+    Ok(result)
+}
+
+/// card side of DESFire legacy (native DES/3DES) authentication
+pub struct DesfireCard {
+    key: TdesKey,
+    rnd_b: Option<Zeroizing<[u8; 8]>>,
+    session_key: Option<TdesKey>,
+}
+
+impl DesfireCard {
+    /// DES keys are double-length keys with equal halves
+    pub fn new(key: TdesKey) -> Self {
+        Self {
+            key,
+            rnd_b: None,
+            session_key: None,
+        }
+    }
+
+    /// returns encoded RndB using OS randomness
+    pub fn begin_authentication(&mut self) -> Result<[u8; 8]> {
+        self.begin_authentication_with(&mut OsRng)
+    }
+
+    /// same as begin_authentication, but takes RndB from rng
+    pub fn begin_authentication_with<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<[u8; 8]> {
+        self.session_key = None;
+        let rnd_b = random_block(rng)?;
+        let challenge = encode_bytes(&self.key, &rnd_b[..])?;
+        self.rnd_b = Some(rnd_b);
+        Ok(challenge)
+    }
+
+    /// checks RndB' of the reader response (RndA and RndB' in send mode) and
+    /// returns encoded RndA'
+    pub fn complete_authentication(&mut self, response: &[u8]) -> Result<[u8; 8]> {
+        let rnd_b = self.rnd_b.take().ok_or(Error::AuthenticationFailed)?;
+        if response.len() != 16 {
+            return Err(Error::AuthenticationFailed);
+        }
+        let data = Zeroizing::new(undo_send_mode(&self.key, response)?);
+        let (rnd_a, rotated_b) = data.split_at(8);
+        if !constant_time_eq(rotated_b, &rotate(&rnd_b[..])[..]) {
+            return Err(Error::AuthenticationFailed);
+        }
+        self.session_key = Some(desfire_session_key(&self.key, rnd_a, &rnd_b[..])?);
+        encode_bytes(&self.key, &rotate(rnd_a)[..])
+    }
+
+    /// present after successful authentication
+    pub fn session_key(&self) -> Option<&TdesKey> {
+        self.session_key.as_ref()
+    }
+}
+
+/// reader side of DESFire legacy (native DES/3DES) authentication
+pub struct DesfireReader {
+    key: TdesKey,
+    rnd_a: Option<Zeroizing<[u8; 8]>>,
+    rnd_b: Option<Zeroizing<Vec<u8>>>,
+}
+
+impl DesfireReader {
+    pub fn new(key: TdesKey) -> Self {
+        Self {
+            key,
+            rnd_a: None,
+            rnd_b: None,
+        }
+    }
+
+    /// answers encoded RndB with RndA and RndB' in send mode, RndA is taken from OS
+    /// randomness
+    pub fn respond_to_challenge(&mut self, challenge: &[u8]) -> Result<Vec<u8>> {
+        self.respond_to_challenge_with(challenge, &mut OsRng)
+    }
+
+    /// same as respond_to_challenge, but takes RndA from rng
+    pub fn respond_to_challenge_with<R: RngCore + CryptoRng>(
+        &mut self,
+        challenge: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>> {
+        if challenge.len() != 8 {
+            return Err(Error::AuthenticationFailed);
+        }
+        // the card encodes, so the reader decodes
+        let rnd_b = Zeroizing::new(receive_mode_cbc(&self.key, challenge)?);
+        let rnd_a = random_block(rng)?;
+        let mut data = Zeroizing::new(rnd_a.to_vec());
+        data.extend(&rotate(&rnd_b)[..]);
+
+        let response = send_mode_cbc(&self.key, &data)?;
+        self.rnd_a = Some(rnd_a);
+        self.rnd_b = Some(rnd_b);
+        Ok(response)
+    }
+
+    /// checks encoded RndA' of the card and returns the session key
+    pub fn verify_card(&mut self, response: &[u8]) -> Result<TdesKey> {
+        let (rnd_a, rnd_b) = self
+            .rnd_a
+            .take()
+            .zip(self.rnd_b.take())
+            .ok_or(Error::AuthenticationFailed)?;
+        if response.len() != 8 {
+            return Err(Error::AuthenticationFailed);
+        }
+        let rotated_a = Zeroizing::new(receive_mode_cbc(&self.key, response)?);
+        if !constant_time_eq(&rotated_a, &rotate(&rnd_a[..])[..]) {
+            return Err(Error::AuthenticationFailed);
+        }
+        desfire_session_key(&self.key, &rnd_a[..], &rnd_b[..])
+    }
+}
+
+/// card side of Ultralight C authentication, 2K3DES in CBC mode with IV chained
+/// through the exchange
+pub struct UltralightCard {
+    key: TdesKey,
+    challenge: Option<([u8; 8], Zeroizing<[u8; 8]>)>,
+}
+
+impl UltralightCard {
+    pub fn new(key: TdesKey) -> Self {
+        Self {
+            key,
+            challenge: None,
+        }
+    }
+
+    /// returns encoded RndB using OS randomness
+    pub fn begin_authentication(&mut self) -> Result<[u8; 8]> {
+        self.begin_authentication_with(&mut OsRng)
+    }
+
+    /// same as begin_authentication, but takes RndB from rng
+    pub fn begin_authentication_with<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<[u8; 8]> {
+        let rnd_b = random_block(rng)?;
+        let challenge = encode_bytes(&self.key, &rnd_b[..])?;
+        self.challenge = Some((challenge, rnd_b));
+        Ok(challenge)
+    }
+
+    /// checks RndB' of the reader response (encoded RndA and RndB') and returns
+    /// encoded RndA'
+    pub fn complete_authentication(&mut self, response: &[u8]) -> Result<[u8; 8]> {
+        let (challenge, rnd_b) = self.challenge.take().ok_or(Error::AuthenticationFailed)?;
+        if response.len() != 16 {
+            return Err(Error::AuthenticationFailed);
+        }
+        let data = Zeroizing::new(cbc_decode(
+            &self.key,
+            &Block::from_bytes(&challenge)?,
+            response,
+        )?);
+        let (rnd_a, rotated_b) = data.split_at(8);
+        if !constant_time_eq(rotated_b, &rotate(&rnd_b[..])[..]) {
+            return Err(Error::AuthenticationFailed);
+        }
+        let iv = Block::from_bytes(&response[8..])?;
+        let mut result = [0; 8];
+        result.copy_from_slice(&cbc_encode(&self.key, &iv, &rotate(rnd_a)[..])?);
+        Ok(result)
+    }
+}
+
+/// reader side of Ultralight C authentication
+pub struct UltralightReader {
+    key: TdesKey,
+    /// RndA and the last sent block
+    state: Option<(Zeroizing<[u8; 8]>, [u8; 8])>,
+}
+
+impl UltralightReader {
+    pub fn new(key: TdesKey) -> Self {
+        Self { key, state: None }
+    }
+
+    /// answers encoded RndB with encoded RndA and RndB', RndA is taken from OS randomness
+    pub fn respond_to_challenge(&mut self, challenge: &[u8]) -> Result<Vec<u8>> {
+        self.respond_to_challenge_with(challenge, &mut OsRng)
+    }
+
+    /// same as respond_to_challenge, but takes RndA from rng
+    pub fn respond_to_challenge_with<R: RngCore + CryptoRng>(
+        &mut self,
+        challenge: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>> {
+        if challenge.len() != 8 {
+            return Err(Error::AuthenticationFailed);
+        }
+        let rnd_b = Zeroizing::new(
+            self.key
+                .decode_block(&Block::from_bytes(challenge)?)?
+                .to_bytes(),
+        );
+        let rnd_a = random_block(rng)?;
+        let mut data = Zeroizing::new(rnd_a.to_vec());
+        data.extend(&rotate(&rnd_b[..])[..]);
+
+        let response = cbc_encode(&self.key, &Block::from_bytes(challenge)?, &data)?;
+        let mut last = [0; 8];
+        last.copy_from_slice(&response[8..]);
+        self.state = Some((rnd_a, last));
+        Ok(response)
+    }
+
+    /// checks encoded RndA' of the card
+    pub fn verify_card(&mut self, response: &[u8]) -> Result<()> {
+        let (rnd_a, last) = self.state.take().ok_or(Error::AuthenticationFailed)?;
+        if response.len() != 8 {
+            return Err(Error::AuthenticationFailed);
+        }
+        let rotated_a =
+            Zeroizing::new(cbc_decode(&self.key, &Block::from_bytes(&last)?, response)?);
+        match constant_time_eq(&rotated_a, &rotate(&rnd_a[..])[..]) {
+            true => Ok(()),
+            false => Err(Error::AuthenticationFailed),
+        }
+    }
+}
+
+/// DES session key is RndA[0..4] || RndB[0..4], 2K3DES one is followed by
+/// RndA[4..8] || RndB[4..8]
+fn desfire_session_key(key: &TdesKey, rnd_a: &[u8], rnd_b: &[u8]) -> Result<TdesKey> {
+    let mut session_key = Zeroizing::new(rnd_a[..4].to_vec());
+    session_key.extend(&rnd_b[..4]);
+    let [k1, k2, _] = key.components();
+    match k1 == k2 {
+        true => session_key.extend_from_within(..8),
+        false => {
+            session_key.extend(&rnd_a[4..]);
+            session_key.extend(&rnd_b[4..]);
+        }
+    }
+    TdesKey::from_bytes(&session_key)
+}
+
+/// rotates left by one byte
+fn rotate(data: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut rotated = Zeroizing::new(data.to_vec());
+    rotated.rotate_left(1);
+    rotated
+}
+
+fn random_block<R: RngCore + CryptoRng>(rng: &mut R) -> Result<Zeroizing<[u8; 8]>> {
+    let mut block = Zeroizing::new([0; 8]);
+    rng.try_fill_bytes(&mut block[..])
+        .map_err(|err| Error::RandomSource(err.to_string()))?;
+    Ok(block)
+}
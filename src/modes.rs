@@ -0,0 +1,98 @@
+use super::{Block, BlockCipher, Error, Result};
+
+/// passed data length should be a multiple of 8
+pub fn ecb_encode(cipher: &impl BlockCipher, data: &[u8]) -> Result<Vec<u8>> {
+    check_blocks(data)?;
+    let mut result = Vec::with_capacity(data.len());
+    for chunk in data.chunks(8) {
+        let block = cipher.encode_block(&Block::from_bytes(chunk)?)?;
+        result.extend(block.to_bytes());
+    }
+    Ok(result)
+}
+
+/// passed data length should be a multiple of 8
+pub fn ecb_decode(cipher: &impl BlockCipher, data: &[u8]) -> Result<Vec<u8>> {
+    check_blocks(data)?;
+    let mut result = Vec::with_capacity(data.len());
+    for chunk in data.chunks(8) {
+        let block = cipher.decode_block(&Block::from_bytes(chunk)?)?;
+        result.extend(block.to_bytes());
+    }
+    Ok(result)
+}
+
+/// passed data length should be a multiple of 8
+pub fn cbc_encode(cipher: &impl BlockCipher, iv: &Block, data: &[u8]) -> Result<Vec<u8>> {
+    check_blocks(data)?;
+    let mut result = Vec::with_capacity(data.len());
+    let mut previous = iv.to_bytes();
+    for chunk in data.chunks(8) {
+        previous = encode_bytes(cipher, &xor(chunk, &previous))?;
+        result.extend(previous);
+    }
+    Ok(result)
+}
+
+/// passed data length should be a multiple of 8
+pub fn cbc_decode(cipher: &impl BlockCipher, iv: &Block, data: &[u8]) -> Result<Vec<u8>> {
+    check_blocks(data)?;
+    let mut result = Vec::with_capacity(data.len());
+    let mut previous = iv.to_bytes().to_vec();
+    for chunk in data.chunks(8) {
+        let decoded = cipher.decode_block(&Block::from_bytes(chunk)?)?.to_bytes();
+        result.extend(xor(&decoded, &previous));
+        previous = chunk.to_vec();
+    }
+    Ok(result)
+}
+
+/// 64-bit cipher feedback, the last block may be shorter than 8 bytes
+pub fn cfb_encode(cipher: &impl BlockCipher, iv: &Block, data: &[u8]) -> Result<Vec<u8>> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut feedback = iv.clone();
+    for chunk in data.chunks(8) {
+        let gamma = cipher.encode_block(&feedback)?.to_bytes();
+        let encoded = xor(chunk, &gamma);
+        if encoded.len() == 8 {
+            feedback = Block::from_bytes(&encoded)?;
+        }
+        result.extend(encoded);
+    }
+    Ok(result)
+}
+
+/// 64-bit cipher feedback, the last block may be shorter than 8 bytes
+pub fn cfb_decode(cipher: &impl BlockCipher, iv: &Block, data: &[u8]) -> Result<Vec<u8>> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut feedback = iv.clone();
+    for chunk in data.chunks(8) {
+        let gamma = cipher.encode_block(&feedback)?.to_bytes();
+        if chunk.len() == 8 {
+            feedback = Block::from_bytes(chunk)?;
+        }
+        result.extend(xor(chunk, &gamma));
+    }
+    Ok(result)
+}
+
+/// encodes a single 8 byte block
+pub(crate) fn encode_bytes(cipher: &impl BlockCipher, bytes: &[u8]) -> Result<[u8; 8]> {
+    Ok(cipher.encode_block(&Block::from_bytes(bytes)?)?.to_bytes())
+}
+
+/// xors data with the beginning of gamma
+pub(crate) fn xor(data: &[u8], gamma: &[u8]) -> Vec<u8> {
+    data.iter().zip(gamma).map(|(a, b)| a ^ b).collect()
+}
+
+/// the error reports lengths in bits
+pub(crate) fn check_blocks(data: &[u8]) -> Result<()> {
+    if !data.len().is_multiple_of(8) {
+        return Err(Error::InvalidIterableLength {
+            expected: data.len().next_multiple_of(8) * 8,
+            got: data.len() * 8,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use rand_core::{CryptoRng, OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::modes::{encode_bytes, xor};
+use super::{Block, BlockCipher, Error, Result};
+
+/// ISO 9564-1 PIN block format, the control field of the block
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum PinBlockFormat {
+    /// PIN padded with F, xored with PAN
+    Iso0,
+    /// PIN padded with random digits, no PAN
+    Iso1,
+    /// PIN padded with F, no PAN, for offline ICC PIN
+    Iso2,
+    /// PIN padded with random A..=F, xored with PAN
+    Iso3,
+}
+
+impl PinBlockFormat {
+    fn control(&self) -> u8 {
+        match self {
+            Self::Iso0 => 0,
+            Self::Iso1 => 1,
+            Self::Iso2 => 2,
+            Self::Iso3 => 3,
+        }
+    }
+
+    fn uses_pan(&self) -> bool {
+        matches!(self, Self::Iso0 | Self::Iso3)
+    }
+
+    fn is_valid_fill(&self, nibble: u8) -> bool {
+        match self {
+            Self::Iso0 | Self::Iso2 => nibble == 0xF,
+            Self::Iso1 => true,
+            Self::Iso3 => nibble >= 0xA,
+        }
+    }
+}
+
+/// builds clear PIN block using OS randomness for fill digits, PAN is ignored by
+/// formats 1 and 2
+pub fn build_pin_block(format: PinBlockFormat, pin: &str, pan: &str) -> Result<[u8; 8]> {
+    build_pin_block_with(format, pin, pan, &mut OsRng)
+}
+
+/// same as build_pin_block, but takes fill digits from rng
+pub fn build_pin_block_with<R: RngCore + CryptoRng>(
+    format: PinBlockFormat,
+    pin: &str,
+    pan: &str,
+    rng: &mut R,
+) -> Result<[u8; 8]> {
+    if !(4..=12).contains(&pin.len()) || !pin.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err(Error::InvalidPinBlock(
+            "PIN should be 4 to 12 digits".to_string(),
+        ));
+    }
+    let mut nibbles = Zeroizing::new(vec![format.control(), pin.len() as u8]);
+    nibbles.extend(pin.bytes().map(|digit| digit - b'0'));
+    while nibbles.len() < 16 {
+        let nibble = match format {
+            PinBlockFormat::Iso0 | PinBlockFormat::Iso2 => 0xF,
+            PinBlockFormat::Iso1 => random_nibble(rng, 0x0)?,
+            PinBlockFormat::Iso3 => random_nibble(rng, 0xA)?,
+        };
+        nibbles.push(nibble);
+    }
+
+    let block = Zeroizing::new(pack(&nibbles));
+    match format.uses_pan() {
+        true => to_block(&xor(&block[..], &pan_field(pan)?)),
+        false => to_block(&block),
+    }
+}
+
+/// extracts PIN from clear PIN block, validating control field, PIN length and fill digits
+pub fn parse_pin_block(
+    format: PinBlockFormat,
+    block: &[u8; 8],
+    pan: &str,
+) -> Result<Zeroizing<String>> {
+    let block = match format.uses_pan() {
+        true => Zeroizing::new(to_block(&xor(block, &pan_field(pan)?))?),
+        false => Zeroizing::new(*block),
+    };
+    let nibbles = Zeroizing::new(
+        block
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0xF])
+            .collect::<Vec<u8>>(),
+    );
+    if nibbles[0] != format.control() {
+        return Err(Error::InvalidPinBlock(format!(
+            "expected format {}, got {}",
+            format.control(),
+            nibbles[0]
+        )));
+    }
+    let len = nibbles[1] as usize;
+    if !(4..=12).contains(&len) {
+        return Err(Error::InvalidPinBlock(format!("invalid PIN length {len}")));
+    }
+    let (pin, fill) = nibbles[2..].split_at(len);
+    if pin.iter().any(|digit| *digit > 9) {
+        return Err(Error::InvalidPinBlock(
+            "PIN contains non-digits".to_string(),
+        ));
+    }
+    if !fill.iter().all(|nibble| format.is_valid_fill(*nibble)) {
+        return Err(Error::InvalidPinBlock("invalid fill digits".to_string()));
+    }
+    Ok(Zeroizing::new(
+        pin.iter().map(|digit| (b'0' + digit) as char).collect(),
+    ))
+}
+
+/// builds PIN block and encodes it under PIN encryption key
+pub fn encrypt_pin(
+    key: &impl BlockCipher,
+    format: PinBlockFormat,
+    pin: &str,
+    pan: &str,
+) -> Result<[u8; 8]> {
+    let block = Zeroizing::new(build_pin_block(format, pin, pan)?);
+    encode_bytes(key, &block[..])
+}
+
+/// decodes PIN block with PIN encryption key and extracts PIN
+pub fn decrypt_pin(
+    key: &impl BlockCipher,
+    format: PinBlockFormat,
+    encrypted: &[u8; 8],
+    pan: &str,
+) -> Result<Zeroizing<String>> {
+    let block = Zeroizing::new(key.decode_block(&Block::from_bytes(encrypted)?)?.to_bytes());
+    parse_pin_block(format, &block, pan)
+}
+
+/// re-encrypts PIN block under another key and format, clear PIN never leaves the function,
+/// formats bound to PAN can't be translated to ones without it
+pub fn translate_pin_block(
+    from_key: &impl BlockCipher,
+    from_format: PinBlockFormat,
+    to_key: &impl BlockCipher,
+    to_format: PinBlockFormat,
+    encrypted: &[u8; 8],
+    pan: &str,
+) -> Result<[u8; 8]> {
+    if from_format.uses_pan() && !to_format.uses_pan() {
+        return Err(Error::InvalidPinBlock(format!(
+            "translation from {from_format:?} to {to_format:?} drops the PAN"
+        )));
+    }
+    let pin = decrypt_pin(from_key, from_format, encrypted, pan)?;
+    encrypt_pin(to_key, to_format, &pin, pan)
+}
+
+/// 4 zero digits and rightmost 12 PAN digits excluding the check digit
+fn pan_field(pan: &str) -> Result<[u8; 8]> {
+    if pan.len() < 2 || !pan.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err(Error::InvalidPinBlock(format!("invalid PAN {pan}")));
+    }
+    let digits = &pan[..pan.len() - 1];
+    let digits = format!("{:0>16}", &digits[digits.len().saturating_sub(12)..]);
+    to_block(&pack(
+        &digits
+            .bytes()
+            .map(|digit| digit - b'0')
+            .collect::<Vec<u8>>(),
+    ))
+}
+
+/// random nibble from start..=0xF
+fn random_nibble<R: RngCore + CryptoRng>(rng: &mut R, start: u8) -> Result<u8> {
+    let range = 0x10 - start;
+    // rejection sampling keeps the digits uniform
+    let limit = u8::MAX - u8::MAX % range;
+    loop {
+        let mut byte = [0];
+        rng.try_fill_bytes(&mut byte)
+            .map_err(|err| Error::RandomSource(err.to_string()))?;
+        if byte[0] < limit {
+            return Ok(start + byte[0] % range);
+        }
+    }
+}
+
+fn pack(nibbles: &[u8]) -> Vec<u8> {
+    nibbles
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect()
+}
+
+fn to_block(bytes: &[u8]) -> Result<[u8; 8]> {
+    bytes.try_into().map_err(|_| Error::InvalidIterableLength {
+        expected: 64,
+        got: bytes.len() * 8,
+    })
+}
@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use zeroize::Zeroizing;
+
+use super::mac::constant_time_eq;
+use super::modes::encode_bytes;
+use super::traits::{FromHexStr, ToHexString};
+use super::{BlockCipher, Error, Result, TdesKey};
+
+/// maps each hex digit of the encoded validation data to a decimal digit
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct DecimalizationTable {
+    digits: [u8; 16],
+}
+
+impl Default for DecimalizationTable {
+    /// 0123456789012345
+    fn default() -> Self {
+        Self {
+            digits: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5],
+        }
+    }
+}
+
+impl FromStr for DecimalizationTable {
+    type Err = Error;
+
+    /// passed str should be 16 decimal digits
+    fn from_str(s: &str) -> Result<Self> {
+        if s.len() != 16 || !s.chars().all(|ch| ch.is_ascii_digit()) {
+            return Err(Error::StringParseError(s.to_string()));
+        }
+        let mut digits = [0; 16];
+        for (digit, ch) in digits.iter_mut().zip(s.bytes()) {
+            *digit = ch - b'0';
+        }
+        Ok(Self { digits })
+    }
+}
+
+impl DecimalizationTable {
+    /// decimalizes every hex digit of bytes
+    fn decimalize(&self, bytes: &[u8]) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(
+            bytes
+                .iter()
+                .flat_map(|byte| [byte >> 4, byte & 0xF])
+                .map(|nibble| self.digits[nibble as usize])
+                .collect(),
+        )
+    }
+}
+
+/// IBM 3624 natural PIN: leftmost digits of decimalized validation data (16 hex
+/// digits) encoded with PIN verification key
+pub fn ibm3624_natural_pin(
+    pvk: &impl BlockCipher,
+    validation_data: &str,
+    table: &DecimalizationTable,
+    pin_length: usize,
+) -> Result<Zeroizing<String>> {
+    if !(4..=12).contains(&pin_length) {
+        return Err(Error::InvalidPin(format!(
+            "invalid PIN length {pin_length}"
+        )));
+    }
+    if validation_data.len() != 16 {
+        return Err(Error::InvalidIterableLength {
+            expected: 64,
+            got: validation_data.len() * 4,
+        });
+    }
+    let encoded = Zeroizing::new(encode_bytes(pvk, &Vec::from_hex_str(validation_data)?)?);
+    let digits = table.decimalize(&encoded[..]);
+    Ok(Zeroizing::new(
+        digits[..pin_length]
+            .iter()
+            .map(|digit| (b'0' + digit) as char)
+            .collect(),
+    ))
+}
+
+/// IBM 3624 PIN offset: customer PIN minus natural PIN, digit by digit modulo 10
+pub fn ibm3624_offset(
+    pvk: &impl BlockCipher,
+    validation_data: &str,
+    table: &DecimalizationTable,
+    pin: &str,
+) -> Result<String> {
+    let pin = pin_digits(pin)?;
+    let natural = ibm3624_natural_pin(pvk, validation_data, table, pin.len())?;
+    Ok(pin
+        .iter()
+        .zip(natural.bytes())
+        .map(|(digit, natural)| (b'0' + (digit + 10 - (natural - b'0')) % 10) as char)
+        .collect())
+}
+
+/// compares offset of the entered PIN in constant time
+pub fn verify_ibm3624_offset(
+    pvk: &impl BlockCipher,
+    validation_data: &str,
+    table: &DecimalizationTable,
+    pin: &str,
+    offset: &str,
+) -> Result<bool> {
+    let expected = ibm3624_offset(pvk, validation_data, table, pin)?;
+    Ok(constant_time_eq(expected.as_bytes(), offset.as_bytes()))
+}
+
+/// Visa PIN verification value: 4 digits of the transformed security parameter
+/// (11 PAN digits, PVK index and 4 PIN digits) encoded with the PVK pair
+pub fn visa_pvv(pvk: &TdesKey, pan: &str, pvki: u8, pin: &str) -> Result<String> {
+    pin_digits(pin)?;
+    if pan.len() < 12 || !pan.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err(Error::StringParseError(pan.to_string()));
+    }
+    if pvki > 9 {
+        return Err(Error::InvalidPin(format!("invalid PVK index {pvki}")));
+    }
+    let pan = &pan[pan.len() - 12..pan.len() - 1];
+    let tsp = Zeroizing::new(format!("{pan}{pvki}{}", &pin[..4]));
+    let encoded = Zeroizing::new(encode_bytes(pvk, &Vec::from_hex_str(&tsp)?)?.to_upper_hex());
+    Ok(decimalize(&encoded, 4))
+}
+
+/// compares PVV of the entered PIN in constant time
+pub fn verify_visa_pvv(pvk: &TdesKey, pan: &str, pvki: u8, pin: &str, pvv: &str) -> Result<bool> {
+    let expected = visa_pvv(pvk, pan, pvki, pin)?;
+    Ok(constant_time_eq(expected.as_bytes(), pvv.as_bytes()))
+}
+
+/// takes decimal digits of uppercase hex first, then its letters as 0..=5
+pub(crate) fn decimalize(hex: &str, count: usize) -> String {
+    let letters = hex
+        .chars()
+        .filter(|ch| !ch.is_ascii_digit())
+        .map(|ch| (ch as u8 - b'A' + b'0') as char);
+    hex.chars()
+        .filter(char::is_ascii_digit)
+        .chain(letters)
+        .take(count)
+        .collect()
+}
+
+/// PIN should be 4 to 12 decimal digits
+fn pin_digits(pin: &str) -> Result<Zeroizing<Vec<u8>>> {
+    if !(4..=12).contains(&pin.len()) || !pin.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err(Error::InvalidPin(
+            "PIN should be 4 to 12 digits".to_string(),
+        ));
+    }
+    Ok(Zeroizing::new(pin.bytes().map(|ch| ch - b'0').collect()))
+}
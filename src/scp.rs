@@ -0,0 +1,263 @@
+use zeroize::Zeroizing;
+
+use super::mac::{cbc_mac, constant_time_eq, retail_mac_padded, MacPadding};
+use super::modes::{cbc_encode, ecb_encode, encode_bytes};
+use super::{Block, Error, Result, TdesKey};
+
+/// GlobalPlatform secure channel protocol with 3DES
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum ScpVersion {
+    /// session keys from both challenges, full 3DES C-MAC
+    Scp01,
+    /// session keys from sequence counter, retail C-MAC
+    Scp02,
+}
+
+/// what is applied to wrapped commands
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum SecurityLevel {
+    CMac,
+    CMacAndEncryption,
+}
+
+/// card keys, all of them double-length
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct StaticKeys {
+    pub enc: TdesKey,
+    pub mac: TdesKey,
+    pub dek: TdesKey,
+}
+
+/// keys of a single session, SCP01 has no R-MAC key and uses static DEK
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct SessionKeys {
+    pub enc: TdesKey,
+    pub c_mac: TdesKey,
+    pub r_mac: Option<TdesKey>,
+    pub dek: TdesKey,
+}
+
+/// SCP01 session keys encode card challenge[4..8], host challenge[0..4], card
+/// challenge[0..4] and host challenge[4..8] with static keys
+pub fn derive_scp01_keys(
+    keys: &StaticKeys,
+    host_challenge: &[u8; 8],
+    card_challenge: &[u8; 8],
+) -> Result<SessionKeys> {
+    let mut data = card_challenge[4..].to_vec();
+    data.extend(&host_challenge[..4]);
+    data.extend(&card_challenge[..4]);
+    data.extend(&host_challenge[4..]);
+
+    let derive = |key: &TdesKey| -> Result<TdesKey> {
+        TdesKey::from_bytes(&Zeroizing::new(ecb_encode(key, &data)?))
+    };
+    Ok(SessionKeys {
+        enc: derive(&keys.enc)?,
+        c_mac: derive(&keys.mac)?,
+        r_mac: None,
+        dek: keys.dek.clone(),
+    })
+}
+
+/// SCP02 session keys encode derivation constant and 2 byte sequence counter
+/// with static keys in CBC mode
+pub fn derive_scp02_keys(keys: &StaticKeys, sequence_counter: &[u8; 2]) -> Result<SessionKeys> {
+    let derive = |key: &TdesKey, constant: [u8; 2]| -> Result<TdesKey> {
+        let mut data = [0; 16];
+        data[..2].copy_from_slice(&constant);
+        data[2..4].copy_from_slice(sequence_counter);
+        let iv = Block::from_bytes(&[0; 8])?;
+        TdesKey::from_bytes(&Zeroizing::new(cbc_encode(key, &iv, &data)?))
+    };
+    Ok(SessionKeys {
+        enc: derive(&keys.enc, [0x01, 0x82])?,
+        c_mac: derive(&keys.mac, [0x01, 0x01])?,
+        r_mac: Some(derive(&keys.mac, [0x01, 0x02])?),
+        dek: derive(&keys.dek, [0x01, 0x81])?,
+    })
+}
+
+/// full 3DES MAC with S-ENC over host and card challenges, card challenge is the 8
+/// byte field of INITIALIZE UPDATE response (sequence counter and challenge in SCP02)
+pub fn card_cryptogram(
+    keys: &SessionKeys,
+    host_challenge: &[u8; 8],
+    card_challenge: &[u8; 8],
+) -> Result<[u8; 8]> {
+    let mut data = host_challenge.to_vec();
+    data.extend(card_challenge);
+    cbc_mac(&keys.enc, &MacPadding::Method2.pad(&data))
+}
+
+/// same as card_cryptogram with card challenge first
+pub fn host_cryptogram(
+    keys: &SessionKeys,
+    host_challenge: &[u8; 8],
+    card_challenge: &[u8; 8],
+) -> Result<[u8; 8]> {
+    let mut data = card_challenge.to_vec();
+    data.extend(host_challenge);
+    cbc_mac(&keys.enc, &MacPadding::Method2.pad(&data))
+}
+
+/// compares card cryptogram of INITIALIZE UPDATE response in constant time
+pub fn verify_card_cryptogram(
+    keys: &SessionKeys,
+    host_challenge: &[u8; 8],
+    card_challenge: &[u8; 8],
+    cryptogram: &[u8],
+) -> Result<bool> {
+    let expected = card_cryptogram(keys, host_challenge, card_challenge)?;
+    Ok(constant_time_eq(&expected, cryptogram))
+}
+
+/// host side of an established secure channel, wraps commands after EXTERNAL
+/// AUTHENTICATE and chains C-MACs
+#[derive(Debug, Clone)]
+pub struct SecureChannel {
+    version: ScpVersion,
+    keys: SessionKeys,
+    level: SecurityLevel,
+    icv: [u8; 8],
+    icv_encryption: bool,
+    /// the first command is MACed with a zero ICV even with ICV encryption
+    first_command: bool,
+}
+
+impl SecureChannel {
+    pub fn new(version: ScpVersion, keys: SessionKeys, level: SecurityLevel) -> Self {
+        Self {
+            version,
+            keys,
+            level,
+            icv: [0; 8],
+            icv_encryption: false,
+            first_command: true,
+        }
+    }
+
+    /// option i = 15 of SCP01 and i = 15/55 of SCP02: the ICV of every command after
+    /// the first is encoded with C-MAC key, SCP02 uses only its first half
+    pub fn with_icv_encryption(mut self) -> Self {
+        self.icv_encryption = true;
+        self
+    }
+
+    /// EXTERNAL AUTHENTICATE command with the host cryptogram, it is only MACed and
+    /// sets the security level of the following commands
+    pub fn external_authenticate(&mut self, host_cryptogram: &[u8; 8]) -> Result<Vec<u8>> {
+        let level = match self.level {
+            SecurityLevel::CMac => 0x01,
+            SecurityLevel::CMacAndEncryption => 0x03,
+        };
+        let mut command = vec![0x84, 0x82, level, 0x00, 0x10];
+        command.extend(host_cryptogram);
+        let mac = self.mac(&command)?;
+        command.extend(mac);
+        Ok(command)
+    }
+
+    /// wraps short APDU: sets secure messaging bit of CLA, appends C-MAC computed
+    /// over the clear command and encrypts its data if needed
+    pub fn wrap(&mut self, apdu: &[u8]) -> Result<Vec<u8>> {
+        let (header, data, le) = split_apdu(apdu)?;
+        // Lc has room for the C-MAC and, with encryption, at least one byte of padding
+        // or the SCP01 length prefix rounding data up to a whole block
+        let max_len = match self.level {
+            SecurityLevel::CMac => 255 - 8,
+            SecurityLevel::CMacAndEncryption => 255 - 16,
+        };
+        if data.len() > max_len {
+            return Err(Error::InvalidApdu(format!(
+                "data is too long ({} bytes, at most {max_len})",
+                data.len()
+            )));
+        }
+        let mut mac_data = vec![header[0] | 0x04, header[1], header[2], header[3]];
+        mac_data.push(data.len() as u8 + 8);
+        mac_data.extend(data);
+        let mac = self.mac(&mac_data)?;
+
+        let data = match self.level {
+            SecurityLevel::CMac => data.to_vec(),
+            SecurityLevel::CMacAndEncryption if data.is_empty() => Vec::new(),
+            SecurityLevel::CMacAndEncryption => self.encrypt(data)?,
+        };
+        let mut wrapped = mac_data[..4].to_vec();
+        wrapped.push((data.len() + 8) as u8);
+        wrapped.extend(data);
+        wrapped.extend(mac);
+        wrapped.extend(le);
+        Ok(wrapped)
+    }
+
+    /// encodes key or other sensitive data with DEK in ECB mode, data length should
+    /// be a multiple of 8
+    pub fn encrypt_sensitive_data(&self, data: &[u8]) -> Result<Vec<u8>> {
+        ecb_encode(&self.keys.dek, data)
+    }
+
+    pub fn keys(&self) -> &SessionKeys {
+        &self.keys
+    }
+
+    /// C-MAC chained from the previous one
+    fn mac(&mut self, data: &[u8]) -> Result<[u8; 8]> {
+        let icv = match (self.version, self.icv_encryption) {
+            (_, false) => self.icv,
+            (_, true) if self.first_command => self.icv,
+            (ScpVersion::Scp01, true) => encode_bytes(&self.keys.c_mac, &self.icv)?,
+            (ScpVersion::Scp02, true) => {
+                let [k1, _, _] = self.keys.c_mac.components();
+                encode_bytes(k1, &self.icv)?
+            }
+        };
+        // ICV is xored into the first block
+        let mut padded = MacPadding::Method2.pad(data);
+        for (byte, icv) in padded.iter_mut().zip(icv) {
+            *byte ^= icv;
+        }
+        let mac = match self.version {
+            ScpVersion::Scp01 => cbc_mac(&self.keys.c_mac, &padded)?,
+            ScpVersion::Scp02 => retail_mac_padded(&self.keys.c_mac, &padded)?,
+        };
+        self.icv = mac;
+        self.first_command = false;
+        Ok(mac)
+    }
+
+    /// SCP01 prefixes data with its length and pads it only when needed, SCP02
+    /// always pads
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let padded = match self.version {
+            ScpVersion::Scp01 => {
+                let mut prefixed = vec![data.len() as u8];
+                prefixed.extend(data);
+                match prefixed.len().is_multiple_of(8) {
+                    true => prefixed,
+                    false => MacPadding::Method2.pad(&prefixed),
+                }
+            }
+            ScpVersion::Scp02 => MacPadding::Method2.pad(data),
+        };
+        let padded = Zeroizing::new(padded);
+        cbc_encode(&self.keys.enc, &Block::from_bytes(&[0; 8])?, &padded)
+    }
+}
+
+/// returns header, data and optional Le of a short APDU
+fn split_apdu(apdu: &[u8]) -> Result<(&[u8], &[u8], &[u8])> {
+    let invalid = || Error::InvalidApdu(format!("invalid length {}", apdu.len()));
+    let (header, body) = apdu.split_at_checked(4).ok_or_else(invalid)?;
+    match body {
+        [] => Ok((header, &[], &[])),
+        [_] => Ok((header, &[], body)),
+        [lc, rest @ ..] if rest.len() == *lc as usize => Ok((header, rest, &[])),
+        [lc, rest @ ..] if rest.len() == *lc as usize + 1 => {
+            let (data, le) = rest.split_at(*lc as usize);
+            Ok((header, data, le))
+        }
+        _ => Err(invalid()),
+    }
+}
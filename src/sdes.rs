@@ -0,0 +1,137 @@
+use bitvec::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::secret::{fmt_redacted, permute_bits, zeroize_bits};
+use super::{permute, Error, Feistel, Result};
+
+const P10: [usize; 10] = [2, 4, 1, 6, 3, 9, 0, 8, 7, 5];
+const P8: [usize; 8] = [5, 2, 6, 3, 7, 4, 9, 8];
+const IP: [usize; 8] = [1, 5, 2, 0, 3, 7, 4, 6];
+const IP1: [usize; 8] = [3, 0, 2, 4, 6, 1, 7, 5];
+const EP: [usize; 8] = [3, 0, 1, 2, 1, 2, 3, 0];
+const P4: [usize; 4] = [1, 3, 2, 0];
+const S0: [u8; 16] = [1, 0, 3, 2, 3, 2, 1, 0, 0, 2, 1, 3, 3, 1, 3, 2];
+const S1: [u8; 16] = [0, 1, 2, 3, 2, 0, 1, 3, 3, 0, 1, 0, 2, 1, 0, 3];
+
+/// Schaefer's Simplified DES: 10-bit key, 8-bit blocks, 2 rounds
+#[derive(Clone, PartialEq, PartialOrd)]
+pub struct SDes {
+    key: BitVec,
+}
+
+impl fmt::Debug for SDes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("SDes", f)
+    }
+}
+
+impl Zeroize for SDes {
+    fn zeroize(&mut self) {
+        zeroize_bits(&mut self.key);
+    }
+}
+
+impl Drop for SDes {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SDes {}
+
+impl FromStr for SDes {
+    type Err = super::Error;
+
+    /// passed str should be 10 chars long
+    /// treats non-zero values as true
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s.chars().map(|ch| ch == '1').collect())
+    }
+}
+
+impl SDes {
+    /// passed bitvec should contain 10 bits
+    pub fn new(key: BitVec) -> Result<Self> {
+        if key.len() != 10 {
+            return Err(Error::InvalidIterableLength {
+                expected: 10,
+                got: key.len(),
+            });
+        }
+        Ok(Self { key })
+    }
+
+    pub fn as_bitvec(&self) -> &BitVec {
+        &self.key
+    }
+
+    /// returns K1 and K2, 8 bits each
+    pub fn round_keys(&self) -> Result<[BitVec; 2]> {
+        let mut key = permute_bits(&P10, &self.key)?;
+
+        key[..5].rotate_left(1);
+        key[5..].rotate_left(1);
+        let k1 = permute_bits(&P8, &key);
+
+        key[..5].rotate_left(2);
+        key[5..].rotate_left(2);
+        let k2 = permute_bits(&P8, &key);
+        zeroize_bits(&mut key);
+        Ok([k1?, k2?])
+    }
+
+    /// passed bitvec should contain 8 bits
+    pub fn encode(&self, block: &BitVec) -> Result<BitVec> {
+        Self::check_block(block)?;
+        let round_keys = self.round_keys()?;
+        let data = permute(&IP, block.clone())?;
+        let data = Self::network(&round_keys).encode(&data)?;
+        permute(&IP1, data)
+    }
+
+    /// passed bitvec should contain 8 bits
+    pub fn decode(&self, block: &BitVec) -> Result<BitVec> {
+        Self::check_block(block)?;
+        let round_keys = self.round_keys()?;
+        let data = permute(&IP, block.clone())?;
+        let data = Self::network(&round_keys).decode(&data)?;
+        permute(&IP1, data)
+    }
+
+    fn check_block(block: &BitVec) -> Result<()> {
+        if block.len() != 8 {
+            return Err(Error::InvalidIterableLength {
+                expected: 8,
+                got: block.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn network(
+        round_keys: &[BitVec; 2],
+    ) -> Feistel<impl Fn(&BitVec, &BitVec) -> Result<BitVec>, impl Fn(u8) -> Result<BitVec> + '_>
+    {
+        Feistel::new(4, 1..=2, Self::f, |round| {
+            Ok(round_keys[round as usize - 1].clone())
+        })
+    }
+
+    fn f(right: &BitVec, key: &BitVec) -> Result<BitVec> {
+        let right = permute(&EP, right.clone())? ^ key;
+        let (left_half, right_half) = right.split_at(4);
+        let mut result = Self::s_box_shift(left_half, &S0);
+        result.extend(Self::s_box_shift(right_half, &S1));
+        permute(&P4, result)
+    }
+
+    /// outer bits select the row, inner bits select the column
+    fn s_box_shift(block: &BitSlice, s_box: &[u8; 16]) -> BitVec {
+        let row = (block[0] as usize) << 1 | block[3] as usize;
+        let column = (block[1] as usize) << 1 | block[2] as usize;
+        let value = s_box[row * 4 + column];
+        [value & 0b10 != 0, value & 0b01 != 0].into_iter().collect()
+    }
+}
@@ -0,0 +1,64 @@
+use bitvec::prelude::*;
+use std::fmt;
+use zeroize::Zeroize;
+
+use super::traits::BlockCipher;
+use super::{Block, Error, Result};
+
+/// owned key that can't be cloned or printed, for deployments that
+/// want to keep exactly one copy of the key material around
+pub struct SecretKey<K> {
+    key: K,
+}
+
+impl<K> fmt::Debug for SecretKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("SecretKey", f)
+    }
+}
+
+impl<K: BlockCipher> BlockCipher for SecretKey<K> {
+    fn encode_block(&self, block: &Block) -> Result<Block> {
+        self.key.encode_block(block)
+    }
+
+    fn decode_block(&self, block: &Block) -> Result<Block> {
+        self.key.decode_block(block)
+    }
+}
+
+impl<K> SecretKey<K> {
+    pub fn new(key: K) -> Self {
+        Self { key }
+    }
+
+    pub fn expose_secret(&self) -> &K {
+        &self.key
+    }
+}
+
+/// overwrites the bits' storage with zeros
+pub(crate) fn zeroize_bits(bits: &mut BitVec) {
+    bits.as_raw_mut_slice().zeroize();
+}
+
+/// picks key bits in the order given by scheme into an exactly sized buffer, unlike
+/// permute it neither consumes a copy of the key nor leaves an intermediate buffer
+pub(crate) fn permute_bits(scheme: &[usize], bits: &BitSlice) -> Result<BitVec> {
+    let mut result = BitVec::with_capacity(scheme.len());
+    for pos in scheme {
+        let bit = bits.get(*pos).ok_or(Error::InvalidIterableLength {
+            expected: pos + 1,
+            got: bits.len(),
+        })?;
+        result.push(*bit);
+    }
+    Ok(result)
+}
+
+/// formats key-bearing types without their key
+pub(crate) fn fmt_redacted(name: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct(name)
+        .field("key", &format_args!("<redacted>"))
+        .finish()
+}
@@ -149,14 +149,29 @@ impl ShiftSchemes {
     where
         T: IntoIterator + FromIterator<T::Item> + Clone + Index<usize, Output = T::Item>,
         T::Item: Copy,
+        for<'a> &'a T: IntoIterator,
     {
-        let scheme = self.as_slice();
-        let mut result: Vec<T::Item> = Vec::with_capacity(scheme.len());
-        for pos in scheme {
-            result.push(items[*pos]);
-        }
-        Ok(T::from_iter(result))
+        permute(self.as_slice(), items)
+    }
+}
+
+/// picks items in the order given by scheme, so result[i] = items[scheme[i]], items are
+/// collected straight into the result without an intermediate buffer. positions past the
+/// end of items are an error
+pub fn permute<T>(scheme: &[usize], items: T) -> Result<T>
+where
+    T: IntoIterator + FromIterator<T::Item> + Clone + Index<usize, Output = T::Item>,
+    T::Item: Copy,
+    for<'a> &'a T: IntoIterator,
+{
+    let len = (&items).into_iter().count();
+    if let Some(pos) = scheme.iter().find(|pos| **pos >= len) {
+        return Err(Error::InvalidIterableLength {
+            expected: pos + 1,
+            got: len,
+        });
     }
+    Ok(scheme.iter().map(|pos| items[*pos]).collect())
 }
 
 #[cfg(test)]
@@ -0,0 +1,103 @@
+use bitvec::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::secret::{fmt_redacted, zeroize_bits};
+use super::traits::{FromHexStr, ToHexString};
+use super::{Error, Result};
+
+/// 48-bit DES round key
+#[derive(Clone, PartialEq, PartialOrd)]
+pub struct Subkey {
+    key: BitVec,
+}
+
+impl fmt::Debug for Subkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("Subkey", f)
+    }
+}
+
+impl Zeroize for Subkey {
+    fn zeroize(&mut self) {
+        zeroize_bits(&mut self.key);
+    }
+}
+
+impl Drop for Subkey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Subkey {}
+
+impl fmt::Display for Subkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatted = self
+            .key
+            .to_string()
+            .trim_matches(['[', ']'])
+            .split(", ")
+            .collect::<String>();
+        write!(f, "{}", formatted)
+    }
+}
+
+impl FromStr for Subkey {
+    type Err = super::Error;
+
+    /// passed str should be 48 chars long
+    /// treats non-zero values as true
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s.chars().map(|ch| ch == '1').collect())
+    }
+}
+
+impl FromHexStr for Subkey {
+    /// passed str should be 12 hex chars long
+    fn from_hex_str(s: &str) -> Result<Self> {
+        if s.len() != 12 {
+            return Err(Error::InvalidIterableLength {
+                expected: 12,
+                got: s.len(),
+            });
+        }
+        let key_num =
+            u64::from_str_radix(s, 16).map_err(|_| Error::StringParseError(s.to_string()))?;
+        Self::from_str(format!("{key_num:0>48b}").as_str())
+    }
+}
+
+impl ToHexString for Subkey {
+    fn to_upper_hex(&self) -> String {
+        format!("{:0>12}", self.key.to_upper_hex())
+    }
+
+    fn to_lower_hex(&self) -> String {
+        format!("{:0>12}", self.key.to_lower_hex())
+    }
+}
+
+impl Subkey {
+    /// passed bitvec should contain 48 bits
+    pub fn new(key: BitVec) -> Result<Self> {
+        if key.len() != 48 {
+            return Err(Error::InvalidIterableLength {
+                expected: 48,
+                got: key.len(),
+            });
+        }
+        Ok(Self { key })
+    }
+
+    pub fn as_bitvec(&self) -> &BitVec {
+        &self.key
+    }
+
+    /// returns inner BitVec consuming Self, the caller becomes responsible for wiping it
+    pub fn into_bitvec(mut self) -> BitVec {
+        std::mem::take(&mut self.key)
+    }
+}
@@ -0,0 +1,120 @@
+use super::{Error, Result, ShiftDirection, ShiftSchemes};
+
+/// permutation, substitution and key schedule tables driving DES.
+/// all positions are 0-based, s-boxes are 4 rows of 16 values each, written row after row.
+/// Default gives the FIPS 46 tables
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct DesTables {
+    pub ip: Vec<usize>,
+    pub ip1: Vec<usize>,
+    pub e: Vec<usize>,
+    pub p: Vec<usize>,
+    pub pc1: Vec<usize>,
+    pub pc2: Vec<usize>,
+    pub s_boxes: Vec<Vec<usize>>,
+    /// left rotation of both key halves applied before each round
+    pub rotations: Vec<u8>,
+}
+
+impl Default for DesTables {
+    fn default() -> Self {
+        Self {
+            ip: ShiftSchemes::IP.as_slice().to_vec(),
+            ip1: ShiftSchemes::IP1.as_slice().to_vec(),
+            e: ShiftSchemes::E.as_slice().to_vec(),
+            p: ShiftSchemes::P.as_slice().to_vec(),
+            pc1: ShiftSchemes::PC1.as_slice().to_vec(),
+            pc2: ShiftSchemes::PC2.as_slice().to_vec(),
+            s_boxes: ShiftSchemes::get_s_schemes()
+                .iter()
+                .map(|scheme| scheme.as_slice().to_vec())
+                .collect(),
+            rotations: vec![1, 1, 2, 2, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2, 2, 1],
+        }
+    }
+}
+
+impl DesTables {
+    /// replaces IP and IP1 with identity permutations (as in DESL)
+    pub fn without_ip(mut self) -> Self {
+        self.ip = (0..64).collect();
+        self.ip1 = (0..64).collect();
+        self
+    }
+
+    /// uses the same s-box in all 8 positions (as in DESL)
+    pub fn with_single_s_box(mut self, s_box: Vec<usize>) -> Self {
+        self.s_boxes = vec![s_box; 8];
+        self
+    }
+
+    /// reorders s-boxes, order[i] is the index of the s-box to be used at position i
+    pub fn with_s_box_order(mut self, order: [usize; 8]) -> Result<Self> {
+        Self::check_table("s-box order", &order, 8, 8)?;
+        let mut sorted = order;
+        sorted.sort_unstable();
+        if sorted != [0, 1, 2, 3, 4, 5, 6, 7] {
+            return Err(Error::InvalidTable("s-box order".to_string()));
+        }
+        self.s_boxes = order.iter().map(|i| self.s_boxes[*i].clone()).collect();
+        Ok(self)
+    }
+
+    /// checks lengths and value ranges of every table and that IP1 undoes IP
+    pub fn validate(&self) -> Result<()> {
+        Self::check_table("IP", &self.ip, 64, 64)?;
+        Self::check_table("IP1", &self.ip1, 64, 64)?;
+        if (0..64).any(|i| self.ip[self.ip1[i]] != i) {
+            return Err(Error::InvalidTable("IP1".to_string()));
+        }
+        Self::check_table("E", &self.e, 48, 32)?;
+        Self::check_table("P", &self.p, 32, 32)?;
+        Self::check_table("PC1", &self.pc1, 56, 64)?;
+        Self::check_table("PC2", &self.pc2, 48, 56)?;
+        if self.s_boxes.len() != 8 {
+            return Err(Error::InvalidIterableLength {
+                expected: 8,
+                got: self.s_boxes.len(),
+            });
+        }
+        for s_box in &self.s_boxes {
+            Self::check_table("S", s_box, 64, 16)?;
+        }
+        if self.rotations.len() != 16 {
+            return Err(Error::InvalidIterableLength {
+                expected: 16,
+                got: self.rotations.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// returns total rotation of key halves for the round. u8 should be 1..=16
+    pub fn get_round_shift(&self, round: u8, direction: ShiftDirection) -> Result<usize> {
+        if !(1..=16).contains(&round) {
+            return Err(Error::InvalidRound(round));
+        }
+        let round = match direction {
+            ShiftDirection::Left => round,
+            ShiftDirection::Right => 16 - round + 1,
+        };
+        Ok(self.rotations[..round as usize]
+            .iter()
+            .map(|it| *it as usize)
+            .sum())
+    }
+
+    /// every value should be below `bound`
+    fn check_table(name: &str, table: &[usize], len: usize, bound: usize) -> Result<()> {
+        if table.len() != len {
+            return Err(Error::InvalidIterableLength {
+                expected: len,
+                got: table.len(),
+            });
+        }
+        if table.iter().any(|it| *it >= bound) {
+            return Err(Error::InvalidTable(name.to_string()));
+        }
+        Ok(())
+    }
+}
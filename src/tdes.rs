@@ -0,0 +1,200 @@
+use rand_core::{CryptoRng, OsRng, RngCore};
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::components::{combine, split, KeyComponent};
+use super::kcv::{kcv, verify_kcv, KcvMethod};
+use super::secret::fmt_redacted;
+use super::traits::{BlockCipher, FromHexStr, ToHexString};
+use super::{Block, Error, MainKey, Result, WeakKeyPolicy};
+
+/// double (K1, K2, K1) or triple (K1, K2, K3) length DES key
+#[derive(Clone, PartialEq, PartialOrd)]
+pub struct TdesKey {
+    keys: Vec<MainKey>,
+}
+
+impl fmt::Debug for TdesKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("TdesKey", f)
+    }
+}
+
+impl Zeroize for TdesKey {
+    fn zeroize(&mut self) {
+        self.keys.iter_mut().for_each(MainKey::zeroize);
+    }
+}
+
+/// components wipe themselves when dropped
+impl ZeroizeOnDrop for TdesKey {}
+
+impl FromHexStr for TdesKey {
+    /// passed str should be 32 or 48 hex chars long
+    fn from_hex_str(s: &str) -> Result<Self> {
+        if s.len() != 32 && s.len() != 48 {
+            return Err(Error::InvalidKeyLength(s.len() * 4));
+        }
+        let keys = (0..s.len())
+            .step_by(16)
+            .map(|i| MainKey::from_hex_str(&s[i..i + 16]))
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(keys)
+    }
+}
+
+impl ToHexString for TdesKey {
+    fn to_upper_hex(&self) -> String {
+        self.keys.iter().map(MainKey::to_upper_hex).collect()
+    }
+
+    fn to_lower_hex(&self) -> String {
+        self.keys.iter().map(MainKey::to_lower_hex).collect()
+    }
+}
+
+impl BlockCipher for TdesKey {
+    fn encode_block(&self, block: &Block) -> Result<Block> {
+        self.encode(block)
+    }
+
+    fn decode_block(&self, block: &Block) -> Result<Block> {
+        self.decode(block)
+    }
+}
+
+impl TdesKey {
+    /// passed vec should contain 2 or 3 keys of 64 bits
+    pub fn new(keys: Vec<MainKey>) -> Result<Self> {
+        if keys.len() != 2 && keys.len() != 3 {
+            return Err(Error::InvalidKeyLength(keys.len() * 64));
+        }
+        if let Some(key) = keys.iter().find(|key| key.as_bitvec().len() != 64) {
+            return Err(Error::InvalidIterableLength {
+                expected: 64,
+                got: key.as_bitvec().len(),
+            });
+        }
+        Ok(Self { keys })
+    }
+
+    /// passed slice should be 16 or 24 bytes long
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 16 && bytes.len() != 24 {
+            return Err(Error::InvalidKeyLength(bytes.len() * 8));
+        }
+        Self::new(bytes.chunks(8).map(MainKey::from_bytes).collect())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.keys.iter().flat_map(MainKey::to_bytes).collect()
+    }
+
+    /// same as new, but every component should have odd parity
+    pub fn new_strict(keys: Vec<MainKey>) -> Result<Self> {
+        let key = Self::new(keys)?;
+        if !key.has_odd_parity() {
+            return Err(Error::InvalidParity);
+        }
+        Ok(key)
+    }
+
+    /// generates double (2 components) or triple (3 components) length key from OS randomness.
+    /// components have odd parity, aren't weak or semi-weak and differ from each other
+    pub fn generate(components: usize) -> Result<Self> {
+        Self::generate_with(&mut OsRng, components, WeakKeyPolicy::default())
+    }
+
+    /// same as generate, but takes randomness from rng and rejects keys by the policy
+    pub fn generate_with<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        components: usize,
+        policy: WeakKeyPolicy,
+    ) -> Result<Self> {
+        if components != 2 && components != 3 {
+            return Err(Error::InvalidKeyLength(components * 64));
+        }
+        let mut keys: Vec<MainKey> = Vec::with_capacity(components);
+        while keys.len() < components {
+            let key = MainKey::generate_with(rng, policy)?;
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        Self::new(keys)
+    }
+
+    /// encode with K1, decode with K2, encode with K3
+    pub fn encode(&self, block: &Block) -> Result<Block> {
+        let [k1, k2, k3] = self.components();
+        block.encode(k1)?.decode(k2)?.encode(k3)
+    }
+
+    /// decode with K3, encode with K2, decode with K1
+    pub fn decode(&self, block: &Block) -> Result<Block> {
+        let [k1, k2, k3] = self.components();
+        block.decode(k3)?.encode(k2)?.decode(k1)
+    }
+
+    /// 6 hex digit check value: the key encoding a zero block
+    pub fn kcv(&self) -> Result<String> {
+        kcv(self, KcvMethod::Legacy, 6)
+    }
+
+    /// check value truncated to `digits` hex digits (1..=16)
+    pub fn kcv_with(&self, method: KcvMethod, digits: usize) -> Result<String> {
+        kcv(self, method, digits)
+    }
+
+    /// compares the check value with expected one in constant time, length of expected sets digits
+    pub fn verify_kcv(&self, method: KcvMethod, expected: &str) -> Result<bool> {
+        verify_kcv(self, method, expected)
+    }
+
+    /// splits key with odd parity into `count` random components using OS randomness
+    pub fn split_components(&self, count: usize) -> Result<Vec<KeyComponent<Self>>> {
+        split(self, count, &mut OsRng)
+    }
+
+    /// same as split_components, but takes randomness from rng
+    pub fn split_components_with<R: RngCore + CryptoRng>(
+        &self,
+        count: usize,
+        rng: &mut R,
+    ) -> Result<Vec<KeyComponent<Self>>> {
+        split(self, count, rng)
+    }
+
+    /// XORs components and checks the result against expected check value
+    pub fn combine_components(
+        components: &[KeyComponent<Self>],
+        expected_kcv: &str,
+    ) -> Result<Self> {
+        combine(components, expected_kcv)
+    }
+
+    /// returns K1, K2 and K3 (K3 is K1 for double length keys)
+    pub fn components(&self) -> [&MainKey; 3] {
+        [
+            &self.keys[0],
+            &self.keys[1],
+            self.keys.get(2).unwrap_or(&self.keys[0]),
+        ]
+    }
+
+    /// 2 for double length keys, 3 for triple length keys
+    pub fn component_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn has_odd_parity(&self) -> bool {
+        self.keys.iter().all(MainKey::has_odd_parity)
+    }
+
+    /// returns new key with odd parity set in every component
+    pub fn with_odd_parity(&self) -> Self {
+        Self {
+            keys: self.keys.iter().map(MainKey::with_odd_parity).collect(),
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use rand_core::{CryptoRng, OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::mac::{cbc_mac, cmac, constant_time_eq};
+use super::modes::{cbc_decode, cbc_encode, xor};
+use super::traits::{FromHexStr, ToHexString};
+use super::{Block, Error, Result, TdesKey};
+
+/// length of the fixed part of the header
+const HEADER_LEN: usize = 16;
+
+/// key block version, all of them are protected by TDES
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Tr31Version {
+    /// key variant binding method, deprecated but same as C
+    A,
+    /// TDES key derivation binding method
+    B,
+    /// key variant binding method
+    C,
+}
+
+impl Tr31Version {
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::A => 'A',
+            Self::B => 'B',
+            Self::C => 'C',
+        }
+    }
+
+    fn from_char(ch: char) -> Result<Self> {
+        match ch {
+            'A' => Ok(Self::A),
+            'B' => Ok(Self::B),
+            'C' => Ok(Self::C),
+            _ => Err(Error::InvalidKeyBlock(format!("unsupported version {ch}"))),
+        }
+    }
+
+    /// length of the MAC in bytes
+    fn mac_len(&self) -> usize {
+        match self {
+            Self::B => 8,
+            _ => 4,
+        }
+    }
+}
+
+/// key block header, the key block length and the number of optional blocks are
+/// computed while wrapping
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Tr31Header {
+    pub version: Tr31Version,
+    /// 2 chars, e.g. "P0" for PIN encryption or "K0" for key encryption
+    pub key_usage: String,
+    /// e.g. 'T' for TDES, 'D' for DES
+    pub algorithm: char,
+    /// e.g. 'E' for encrypt only, 'B' for both encrypt and decrypt
+    pub mode_of_use: char,
+    /// 2 chars, "00" if versions aren't used
+    pub key_version: String,
+    /// 'E' exportable, 'N' non-exportable, 'S' sensitive
+    pub exportability: char,
+    /// pairs of 2 char block id and its data
+    pub optional_blocks: Vec<(String, String)>,
+}
+
+impl Tr31Header {
+    pub fn new(
+        version: Tr31Version,
+        key_usage: &str,
+        algorithm: char,
+        mode_of_use: char,
+        exportability: char,
+    ) -> Self {
+        Self {
+            version,
+            key_usage: key_usage.to_string(),
+            algorithm,
+            mode_of_use,
+            key_version: "00".to_string(),
+            exportability,
+            optional_blocks: Vec::new(),
+        }
+    }
+
+    /// formats header and optional blocks, padding the latter with a PB block
+    /// to a multiple of 8 chars
+    fn format(&self, encrypted_len: usize) -> Result<String> {
+        for field in [&self.key_usage, &self.key_version] {
+            if field.len() != 2 || !field.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+                return Err(Error::InvalidKeyBlock(format!(
+                    "invalid header field {field}"
+                )));
+            }
+        }
+        for ch in [self.algorithm, self.mode_of_use, self.exportability] {
+            if !ch.is_ascii_alphanumeric() {
+                return Err(Error::InvalidKeyBlock(format!("invalid header field {ch}")));
+            }
+        }
+
+        let mut blocks = self.optional_blocks.clone();
+        let mut blocks_len = Self::blocks_len(&blocks)?;
+        if !blocks_len.is_multiple_of(8) {
+            // PB block has at least its 4 char prefix
+            let padding = (8 - (blocks_len + 4) % 8) % 8;
+            blocks.push(("PB".to_string(), "0".repeat(padding)));
+            blocks_len += 4 + padding;
+        }
+
+        let total_len = HEADER_LEN + blocks_len + encrypted_len * 2 + self.version.mac_len() * 2;
+        if total_len > 9999 || blocks.len() > 99 {
+            return Err(Error::InvalidKeyBlock("key block is too long".to_string()));
+        }
+        let mut header = format!(
+            "{}{:04}{}{}{}{}{}{:02}00",
+            self.version.as_char(),
+            total_len,
+            self.key_usage,
+            self.algorithm,
+            self.mode_of_use,
+            self.key_version,
+            self.exportability,
+            blocks.len(),
+        );
+        for (id, data) in blocks {
+            header.push_str(&format!("{id}{:02X}{data}", data.len() + 4));
+        }
+        Ok(header)
+    }
+
+    fn blocks_len(blocks: &[(String, String)]) -> Result<usize> {
+        let mut len = 0;
+        for (id, data) in blocks {
+            if id.len() != 2 || data.len() + 4 > 0xFF || !data.is_ascii() {
+                return Err(Error::InvalidKeyBlock(format!(
+                    "invalid optional block {id}"
+                )));
+            }
+            len += data.len() + 4;
+        }
+        Ok(len)
+    }
+
+    /// returns parsed header and the length of its text, optional blocks included
+    fn parse(block: &str) -> Result<(Self, usize)> {
+        if !block.is_ascii() || block.len() < HEADER_LEN {
+            return Err(Error::InvalidKeyBlock("header is too short".to_string()));
+        }
+        let chars: Vec<char> = block.chars().collect();
+        let version = Tr31Version::from_char(chars[0])?;
+        let total_len = block[1..5]
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidKeyBlock("invalid length".to_string()))?;
+        if total_len != block.len() {
+            return Err(Error::InvalidIterableLength {
+                expected: total_len,
+                got: block.len(),
+            });
+        }
+        let block_count = block[12..14]
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidKeyBlock("invalid optional block count".to_string()))?;
+
+        let mut optional_blocks = Vec::with_capacity(block_count);
+        let mut pos = HEADER_LEN;
+        for _ in 0..block_count {
+            let prefix = block
+                .get(pos..pos + 4)
+                .ok_or_else(|| Error::InvalidKeyBlock("truncated optional block".to_string()))?;
+            let len = usize::from_str_radix(&prefix[2..], 16)
+                .ok()
+                .filter(|len| *len >= 4)
+                .ok_or_else(|| Error::InvalidKeyBlock("invalid optional block".to_string()))?;
+            let data = block
+                .get(pos + 4..pos + len)
+                .ok_or_else(|| Error::InvalidKeyBlock("truncated optional block".to_string()))?;
+            if &prefix[..2] != "PB" {
+                optional_blocks.push((prefix[..2].to_string(), data.to_string()));
+            }
+            pos += len;
+        }
+
+        let header = Self {
+            version,
+            key_usage: block[5..7].to_string(),
+            algorithm: chars[7],
+            mode_of_use: chars[8],
+            key_version: block[9..11].to_string(),
+            exportability: chars[11],
+            optional_blocks,
+        };
+        Ok((header, pos))
+    }
+}
+
+/// wraps key under the key block protection key using OS randomness for padding
+pub fn wrap(kbpk: &TdesKey, header: &Tr31Header, key: &[u8]) -> Result<String> {
+    wrap_with(kbpk, header, key, &mut OsRng)
+}
+
+/// same as wrap, but takes padding from rng
+pub fn wrap_with<R: RngCore + CryptoRng>(
+    kbpk: &TdesKey,
+    header: &Tr31Header,
+    key: &[u8],
+    rng: &mut R,
+) -> Result<String> {
+    if key.is_empty() || key.len() * 8 > u16::MAX as usize {
+        return Err(Error::InvalidKeyBlock("invalid key length".to_string()));
+    }
+    // 2 byte key length in bits, key, random padding to a whole block
+    let mut clear_data = Zeroizing::new(((key.len() * 8) as u16).to_be_bytes().to_vec());
+    clear_data.extend(key);
+    let mut padding = vec![0; (8 - clear_data.len() % 8) % 8];
+    rng.try_fill_bytes(&mut padding)
+        .map_err(|err| Error::RandomSource(err.to_string()))?;
+    clear_data.extend(padding);
+
+    let header = header.format(clear_data.len())?;
+    let (encryption_key, mac_key) = derive_keys(kbpk, header.as_bytes()[0] as char)?;
+    let (encrypted, mac) = match Tr31Version::from_char(header.as_bytes()[0] as char)? {
+        Tr31Version::B => {
+            let mac = b_mac(&mac_key, &header, &clear_data)?;
+            let iv = Block::from_bytes(&mac)?;
+            (cbc_encode(&encryption_key, &iv, &clear_data)?, mac.to_vec())
+        }
+        Tr31Version::A | Tr31Version::C => {
+            let iv = Block::from_bytes(&header.as_bytes()[..8])?;
+            let encrypted = cbc_encode(&encryption_key, &iv, &clear_data)?;
+            let mac = c_mac(&mac_key, &header, &encrypted)?;
+            (encrypted, mac)
+        }
+    };
+    Ok(format!(
+        "{header}{}{}",
+        encrypted.to_upper_hex(),
+        mac.to_upper_hex()
+    ))
+}
+
+/// verifies the MAC and returns parsed header with the unwrapped key
+pub fn unwrap(kbpk: &TdesKey, block: &str) -> Result<(Tr31Header, Zeroizing<Vec<u8>>)> {
+    let (header, header_len) = Tr31Header::parse(block)?;
+    let mac_len = header.version.mac_len() * 2;
+    if block.len() < header_len + mac_len + 16 {
+        return Err(Error::InvalidKeyBlock("key data is too short".to_string()));
+    }
+    let header_text = &block[..header_len];
+    let encrypted = Vec::from_hex_str(&block[header_len..block.len() - mac_len])?;
+    let mac = Vec::from_hex_str(&block[block.len() - mac_len..])?;
+    if !encrypted.len().is_multiple_of(8) {
+        return Err(Error::InvalidKeyBlock(
+            "key data isn't block aligned".to_string(),
+        ));
+    }
+
+    let (encryption_key, mac_key) = derive_keys(kbpk, header.version.as_char())?;
+    let clear_data = match header.version {
+        Tr31Version::B => {
+            let iv = Block::from_bytes(&mac)?;
+            let clear_data = Zeroizing::new(cbc_decode(&encryption_key, &iv, &encrypted)?);
+            let expected = b_mac(&mac_key, header_text, &clear_data)?;
+            if !constant_time_eq(&expected, &mac) {
+                return Err(Error::MacMismatch);
+            }
+            clear_data
+        }
+        Tr31Version::A | Tr31Version::C => {
+            let expected = c_mac(&mac_key, header_text, &encrypted)?;
+            if !constant_time_eq(&expected, &mac) {
+                return Err(Error::MacMismatch);
+            }
+            let iv = Block::from_bytes(&header_text.as_bytes()[..8])?;
+            Zeroizing::new(cbc_decode(&encryption_key, &iv, &encrypted)?)
+        }
+    };
+
+    let key_bits = u16::from_be_bytes([clear_data[0], clear_data[1]]) as usize;
+    if !key_bits.is_multiple_of(8) || key_bits / 8 > clear_data.len() - 2 {
+        return Err(Error::InvalidKeyBlock("invalid key length".to_string()));
+    }
+    let key = Zeroizing::new(clear_data[2..2 + key_bits / 8].to_vec());
+    Ok((header, key))
+}
+
+/// returns key block encryption and MAC keys
+fn derive_keys(kbpk: &TdesKey, version: char) -> Result<(TdesKey, TdesKey)> {
+    let kbpk_bytes = Zeroizing::new(kbpk.to_bytes());
+    match Tr31Version::from_char(version)? {
+        Tr31Version::B => {
+            let (algorithm, length) = match kbpk.component_count() {
+                2 => (0x00, 0x80),
+                _ => (0x01, 0xC0),
+            };
+            let mut encryption_key = Zeroizing::new(Vec::with_capacity(kbpk_bytes.len()));
+            let mut mac_key = Zeroizing::new(Vec::with_capacity(kbpk_bytes.len()));
+            for counter in 1..=kbpk.component_count() as u8 {
+                // counter, key usage, separator, algorithm, key length in bits
+                let input = [counter, 0x00, 0x00, 0x00, 0x00, algorithm, 0x00, length];
+                encryption_key.extend(cmac(kbpk, &input)?);
+                let input = [counter, 0x00, 0x01, 0x00, 0x00, algorithm, 0x00, length];
+                mac_key.extend(cmac(kbpk, &input)?);
+            }
+            Ok((
+                TdesKey::from_bytes(&encryption_key)?,
+                TdesKey::from_bytes(&mac_key)?,
+            ))
+        }
+        Tr31Version::A | Tr31Version::C => {
+            let encryption_key = Zeroizing::new(xor(&kbpk_bytes, &[0x45; 24]));
+            let mac_key = Zeroizing::new(xor(&kbpk_bytes, &[0x4D; 24]));
+            Ok((
+                TdesKey::from_bytes(&encryption_key)?,
+                TdesKey::from_bytes(&mac_key)?,
+            ))
+        }
+    }
+}
+
+/// CMAC over header and clear key data
+fn b_mac(mac_key: &TdesKey, header: &str, clear_data: &[u8]) -> Result<[u8; 8]> {
+    let mut data = Zeroizing::new(header.as_bytes().to_vec());
+    data.extend(clear_data);
+    cmac(mac_key, &data)
+}
+
+/// CBC-MAC over header and binary encrypted key data, truncated to 4 bytes
+fn c_mac(mac_key: &TdesKey, header: &str, encrypted: &[u8]) -> Result<Vec<u8>> {
+    let mut data = header.as_bytes().to_vec();
+    data.extend(encrypted);
+    Ok(cbc_mac(mac_key, &data)?[..4].to_vec())
+}
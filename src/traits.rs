@@ -1,7 +1,7 @@
 use bitvec::vec::BitVec;
 use itertools::Itertools;
 
-use super::{Error, Result};
+use super::{Block, Error, Result};
 pub trait FromHexStr: Sized {
     fn from_hex_str(s: &str) -> Result<Self>;
 }
@@ -11,6 +11,12 @@ pub trait ToHexString {
     fn to_lower_hex(&self) -> String;
 }
 
+/// a keyed cipher working on 64-bit blocks, used by the modes of operation
+pub trait BlockCipher {
+    fn encode_block(&self, block: &Block) -> Result<Block>;
+    fn decode_block(&self, block: &Block) -> Result<Block>;
+}
+
 impl FromHexStr for BitVec {
     fn from_hex_str(s: &str) -> Result<Self> {
         let mut result: Vec<String> = Vec::new();
@@ -65,3 +71,29 @@ impl ToHexString for BitVec {
         String::from_iter(s)
     }
 }
+
+impl FromHexStr for Vec<u8> {
+    /// passed str should have even length
+    fn from_hex_str(s: &str) -> Result<Self> {
+        if !s.len().is_multiple_of(2) || !s.is_ascii() {
+            return Err(Error::StringParseError(s.to_string()));
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(&s[i..i + 2], 16)
+                    .map_err(|_| Error::StringParseError(s.to_string()))
+            })
+            .collect()
+    }
+}
+
+impl ToHexString for [u8] {
+    fn to_upper_hex(&self) -> String {
+        self.iter().map(|byte| format!("{byte:02X}")).collect()
+    }
+
+    fn to_lower_hex(&self) -> String {
+        self.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
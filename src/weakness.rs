@@ -0,0 +1,32 @@
+use super::MainKey;
+
+/// FIPS 74 key weakness categories
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum KeyWeakness {
+    /// one of 4 keys for which encoding and decoding are the same
+    Weak,
+    /// one of 12 keys whose dual key decodes what this key encodes
+    SemiWeak { dual: MainKey },
+    /// one of 48 keys producing only 4 distinct round keys
+    PossiblyWeak,
+}
+
+/// which weaknesses should be refused when keys are created or generated
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub enum WeakKeyPolicy {
+    AllowAll,
+    /// refuses weak and semi-weak keys
+    #[default]
+    RejectWeak,
+    /// refuses weak, semi-weak and possibly weak keys
+    RejectPossiblyWeak,
+}
+
+impl WeakKeyPolicy {
+    pub fn rejects(&self, weakness: &KeyWeakness) -> bool {
+        !matches!(
+            (self, weakness),
+            (Self::AllowAll, _) | (Self::RejectWeak, KeyWeakness::PossiblyWeak)
+        )
+    }
+}
@@ -0,0 +1,222 @@
+use des_ndtp::bac::{check_digit, mrz_information, BacKeys, BacTerminal, SimulatedChip};
+use des_ndtp::{Error, FromHexStr, Result, ToHexString};
+
+mod common;
+
+use common::ReplayRng;
+
+// ICAO Doc 9303 part 11, worked example of appendix D
+const MRZ_INFORMATION: &str = "L898902C<369080619406236";
+const RND_IC: &str = "4608F91988702212";
+const RND_IFD: &str = "781723860C06C226";
+const K_IFD: &str = "0B795240CB7049B01C19B33E32804F0B";
+const K_IC: &str = "0B4F80323EB3191CB04970CB4052790B";
+const EF_COM: &str = "60145F0104303130375F36063034303030305C026175";
+
+#[test]
+fn test_mrz_information() -> Result<()> {
+    assert_eq!(check_digit("L898902C<")?, '3');
+    assert_eq!(check_digit("690806")?, '1');
+    assert_eq!(
+        mrz_information("L898902C", "690806", "940623")?,
+        MRZ_INFORMATION
+    );
+    assert!(mrz_information("L898902C01", "690806", "940623").is_err());
+    assert!(mrz_information("l898902c", "690806", "940623").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_keys() -> Result<()> {
+    let keys = BacKeys::from_mrz(MRZ_INFORMATION)?;
+    assert_eq!(keys.enc.to_upper_hex(), "AB94FDECF2674FDFB9B391F85D7F76F2");
+    assert_eq!(keys.mac.to_upper_hex(), "7962D9ECE03D1ACD4C76089DCE131543");
+    assert_eq!(
+        BacKeys::from_seed(&Vec::from_hex_str("239AB9CB282DAF66231DC5A4DF6BFBAE")?)?,
+        keys
+    );
+    Ok(())
+}
+
+fn authenticate() -> Result<(BacTerminal, SimulatedChip, des_ndtp::bac::SecureMessaging)> {
+    let keys = BacKeys::from_mrz(MRZ_INFORMATION)?;
+    let mut chip = SimulatedChip::new(keys.clone()).with_file(0x011E, Vec::from_hex_str(EF_COM)?);
+    let mut terminal = BacTerminal::new(keys);
+
+    let rnd_ic = chip.get_challenge_with(&mut ReplayRng(Vec::from_hex_str(RND_IC)?))?;
+    let mut rng = ReplayRng(Vec::from_hex_str(&format!("{RND_IFD}{K_IFD}"))?);
+    let command = terminal.authenticate_with(&rnd_ic, &mut rng)?;
+    assert_eq!(
+        command,
+        Vec::from_hex_str(
+            "72C29C2371CC9BDB65B779B8E8D37B29ECC154AA56A8799FAE2F498F76ED92F25F1448EEA8AD90A7"
+        )?
+    );
+    let response =
+        chip.mutual_authenticate_with(&command, &mut ReplayRng(Vec::from_hex_str(K_IC)?))?;
+    assert_eq!(
+        response,
+        Vec::from_hex_str(
+            "46B9342A41396CD7386BF5803104D7CEDC122B9132139BAF2EEDC94EE178534F2F2D235D074D7449"
+        )?
+    );
+    let session = terminal.complete(&response)?;
+    Ok((terminal, chip, session))
+}
+
+#[test]
+fn test_mutual_authentication() -> Result<()> {
+    let (_, _, session) = authenticate()?;
+    assert_eq!(
+        session.ssc().to_vec(),
+        Vec::from_hex_str("887022120C06C226")?
+    );
+
+    let keys = BacKeys::from_mrz(MRZ_INFORMATION)?;
+    let mut chip = SimulatedChip::new(keys);
+    let mut terminal = BacTerminal::new(BacKeys::from_mrz("L898902C<369080619406237")?);
+    let rnd_ic = chip.get_challenge()?;
+    let command = terminal.authenticate(&rnd_ic)?;
+    assert!(matches!(
+        chip.mutual_authenticate(&command),
+        Err(Error::AuthenticationFailed)
+    ));
+    assert!(terminal.complete(&command).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_secure_messaging() -> Result<()> {
+    let (_, mut chip, mut session) = authenticate()?;
+
+    // SELECT EF.COM
+    let command = session.protect_command(&Vec::from_hex_str("00A4020C02011E")?)?;
+    assert_eq!(
+        command,
+        Vec::from_hex_str("0CA4020C158709016375432908C044F68E08BF8B92D635FF24F800")?
+    );
+    let response = chip.process(&command)?;
+    assert_eq!(
+        response,
+        Vec::from_hex_str("990290008E08FA855A5D4C50A8ED9000")?
+    );
+    assert_eq!(
+        session.unprotect_response(&response)?,
+        Vec::from_hex_str("9000")?
+    );
+
+    // READ BINARY of the first 4 bytes
+    let command = session.protect_command(&Vec::from_hex_str("00B0000004")?)?;
+    assert_eq!(
+        command,
+        Vec::from_hex_str("0CB000000D9701048E08ED6705417E96BA5500")?
+    );
+    let response = chip.process(&command)?;
+    assert_eq!(
+        response,
+        Vec::from_hex_str("8709019FF0EC34F9922651990290008E08AD55CC17140B2DED9000")?
+    );
+    assert_eq!(
+        session.unprotect_response(&response)?,
+        Vec::from_hex_str("60145F019000")?
+    );
+
+    // the rest of the file
+    let command = session.protect_command(&Vec::from_hex_str("00B0000412")?)?;
+    let response = session.unprotect_response(&chip.process(&command)?)?;
+    assert_eq!(
+        response[..response.len() - 2],
+        Vec::from_hex_str(EF_COM)?[4..]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_secure_messaging_errors() -> Result<()> {
+    let (_, mut chip, mut session) = authenticate()?;
+
+    let command = session.protect_command(&Vec::from_hex_str("00A4020C020101")?)?;
+    let response = session.unprotect_response(&chip.process(&command)?)?;
+    assert_eq!(response, Vec::from_hex_str("6A82")?);
+
+    // tampered response
+    let command = session.protect_command(&Vec::from_hex_str("00A4020C02011E")?)?;
+    let mut response = chip.process(&command)?;
+    response[3] ^= 0x01;
+    assert!(matches!(
+        session.unprotect_response(&response),
+        Err(Error::MacMismatch)
+    ));
+
+    // tampered command ends the session
+    let mut command = session.protect_command(&Vec::from_hex_str("00B0000004")?)?;
+    command[8] ^= 0x01;
+    assert_eq!(chip.process(&command)?, Vec::from_hex_str("6988")?);
+    let command = session.protect_command(&Vec::from_hex_str("00B0000004")?)?;
+    assert_eq!(chip.process(&command)?, Vec::from_hex_str("6982")?);
+    Ok(())
+}
+
+#[test]
+fn test_read_large_file() -> Result<()> {
+    let (_, chip, mut session) = authenticate()?;
+    let content: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+    let mut chip = chip.with_file(0x0102, content.clone());
+
+    let command = session.protect_command(&Vec::from_hex_str("00A4020C020102")?)?;
+    assert_eq!(
+        session.unprotect_response(&chip.process(&command)?)?,
+        Vec::from_hex_str("9000")?
+    );
+
+    let mut read: Vec<u8> = Vec::new();
+    while read.len() < content.len() {
+        let [p1, p2] = (read.len() as u16).to_be_bytes();
+        let command = session.protect_command(&[0x00, 0xB0, p1, p2, 0x00])?;
+        let response = chip.process(&command)?;
+        // protected response data fits a short APDU
+        assert!(response.len() <= 258);
+        let response = session.unprotect_response(&response)?;
+        let (data, sw) = response.split_at(response.len() - 2);
+        assert_eq!(sw, Vec::from_hex_str("9000")?);
+        assert!(!data.is_empty() && data.len() <= 256);
+        read.extend(data);
+    }
+    assert_eq!(read, content);
+
+    let command = session.protect_command(&Vec::from_hex_str("00B003E800")?)?;
+    let response = session.unprotect_response(&chip.process(&command)?)?;
+    assert_eq!(response, Vec::from_hex_str("6B00")?);
+
+    // extended responses use the 3 byte BER length
+    let mut chip_side = session.clone();
+    let response = chip_side.protect_response(&content[..300], [0x90, 0x00])?;
+    assert_eq!(response[..4], Vec::from_hex_str("87820131")?);
+    let response = session.unprotect_response(&response)?;
+    assert_eq!(response[..300], content[..300]);
+    Ok(())
+}
+
+#[test]
+fn test_read_short_file_identifier() -> Result<()> {
+    let (_, mut chip, mut session) = authenticate()?;
+
+    // EF.COM has short EF identifier 1E, P2 is the offset
+    let command = session.protect_command(&Vec::from_hex_str("00B09E0204")?)?;
+    let response = session.unprotect_response(&chip.process(&command)?)?;
+    assert_eq!(response[..4], Vec::from_hex_str(EF_COM)?[2..6]);
+    assert_eq!(response[4..], Vec::from_hex_str("9000")?);
+
+    // the file stays selected for reads by offset
+    let command = session.protect_command(&Vec::from_hex_str("00B0000002")?)?;
+    let response = session.unprotect_response(&chip.process(&command)?)?;
+    assert_eq!(response, Vec::from_hex_str("60149000")?);
+
+    let command = session.protect_command(&Vec::from_hex_str("00B0810004")?)?;
+    let response = session.unprotect_response(&chip.process(&command)?)?;
+    assert_eq!(response, Vec::from_hex_str("6A82")?);
+    let command = session.protect_command(&Vec::from_hex_str("00B0C00004")?)?;
+    let response = session.unprotect_response(&chip.process(&command)?)?;
+    assert_eq!(response, Vec::from_hex_str("6A86")?);
+    Ok(())
+}
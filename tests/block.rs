@@ -65,3 +65,55 @@ fn test_decode() -> Result<()> {
     assert_eq!(cipher_text.decode(&key)?.to_upper_hex(), "8787878787878787");
     Ok(())
 }
+
+#[test]
+fn test_full_round_range() -> Result<()> {
+    let plain_text = Block::from_hex_str("0123456789ABCDEF")?;
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    let cipher_text = plain_text.encode_rounds(&key, 1..=16)?;
+    assert_eq!(cipher_text.to_upper_hex(), "85E813540F0AB405");
+    assert_eq!(cipher_text, plain_text.encode(&key)?);
+    assert_eq!(cipher_text.decode_rounds(&key, 1..=16)?, plain_text);
+    Ok(())
+}
+
+#[test]
+fn test_reduced_rounds() -> Result<()> {
+    let plain_text = Block::from_hex_str("0123456789ABCDEF")?;
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    for (rounds, expected) in [
+        (1..=1, "4472457288EEDDEA"),
+        (1..=4, "49D8632862D26382"),
+        (1..=8, "54ACC03C4B187449"),
+        (3..=7, "74D2208173A6F13E"),
+        (9..=16, "998E8C1110F12D83"),
+        (16..=16, "45374462CCAA98AF"),
+    ] {
+        let cipher_text = plain_text.encode_rounds(&key, rounds.clone())?;
+        assert_eq!(cipher_text.to_upper_hex(), expected);
+        assert_eq!(cipher_text.decode_rounds(&key, rounds)?, plain_text);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_invalid_round_range() -> Result<()> {
+    let block = Block::from_hex_str("0123456789ABCDEF")?;
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    assert!(block.encode_rounds(&key, 0..=4).is_err());
+    assert!(block.encode_rounds(&key, 1..=17).is_err());
+    #[allow(clippy::reversed_empty_ranges)]
+    let rounds = 5..=4;
+    assert!(block.decode_rounds(&key, rounds).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_bytes() -> Result<()> {
+    let block = Block::from_hex_str("0F3CA59D512CA5C6")?;
+    let bytes = [0x0F, 0x3C, 0xA5, 0x9D, 0x51, 0x2C, 0xA5, 0xC6];
+    assert_eq!(block.to_bytes(), bytes);
+    assert_eq!(Block::from_bytes(&bytes)?, block);
+    assert!(Block::from_bytes(&bytes[..7]).is_err());
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use des_ndtp::rand_core::{impls, CryptoRng, Error, RngCore};
+
+/// replays the passed bytes, then repeats the last one
+pub struct ReplayRng(pub Vec<u8>);
+
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest {
+            *byte = match self.0.len() {
+                1 => self.0[0],
+                _ => self.0.remove(0),
+            };
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for ReplayRng {}
@@ -0,0 +1,68 @@
+use des_ndtp::{Error, FromHexStr, KeyComponent, MainKey, Result, TdesKey, ToHexString};
+
+#[test]
+fn test_component_kcv() -> Result<()> {
+    let component = KeyComponent::new(MainKey::from_hex_str("0123456789ABCDEF")?, "D5D44F")?;
+    assert_eq!(component.kcv(), "D5D44F");
+    assert_eq!(component.key().to_upper_hex(), "0123456789ABCDEF");
+
+    assert!(KeyComponent::new(MainKey::from_hex_str("0123456789ABCDEF")?, "D5D440").is_err());
+    // even parity
+    assert!(KeyComponent::from_key(MainKey::from_hex_str("0123456789ABCDEE")?).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_combine() -> Result<()> {
+    let components = [
+        KeyComponent::new(MainKey::from_hex_str("0123456789ABCDEF")?, "D5D44F")?,
+        KeyComponent::new(MainKey::from_hex_str("1F1F1F1F0E0E0E0E")?, "94AEA8")?,
+    ];
+    let key = MainKey::combine_components(&components, "8CD223")?;
+    assert_eq!(key.to_upper_hex(), "1F3D5B7986A4C2E0");
+    assert!(key.has_odd_parity());
+
+    assert!(MainKey::combine_components(&components, "8CD224").is_err());
+    assert!(MainKey::combine_components(&[], "8CD223").is_err());
+    // a single component is the key itself
+    assert!(matches!(
+        MainKey::combine_components(&components[..1], "D5D44F"),
+        Err(Error::InvalidIterableLength {
+            expected: 2,
+            got: 1
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_split() -> Result<()> {
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    for count in [2, 3, 5] {
+        let components = key.split_components(count)?;
+        assert_eq!(components.len(), count);
+        assert!(components
+            .iter()
+            .all(|component| component.key().has_odd_parity() && component.kcv().len() == 6));
+        assert!(components.iter().all(|component| component.key() != &key));
+        assert_eq!(MainKey::combine_components(&components, &key.kcv()?)?, key);
+    }
+    assert!(key.split_components(1).is_err());
+    assert!(MainKey::from_hex_str("133457799BBCDFF0")?
+        .split_components(2)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_split_tdes() -> Result<()> {
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA987654321089ABCDEF01234567")?;
+    let components = key.split_components(3)?;
+    assert_eq!(components.len(), 3);
+    assert!(components
+        .iter()
+        .all(|component| component.key().component_count() == 3));
+    let combined = TdesKey::combine_components(&components, &key.kcv()?)?;
+    assert_eq!(combined, key);
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use des_ndtp::cvv::{cvv, cvv2, dcvv, icvv, verify_cvv};
+use des_ndtp::{FromHexStr, Result, TdesKey};
+
+const CVK: &str = "0123456789ABCDEFFEDCBA9876543210";
+const PAN: &str = "4123456789012345";
+
+#[test]
+fn test_cvv() -> Result<()> {
+    let cvk = TdesKey::from_hex_str(CVK)?;
+    assert_eq!(cvv(&cvk, PAN, "8701", "101")?, "561");
+    assert_eq!(cvv(&cvk, "5432109876543210987", "2612", "201")?, "222");
+    assert!(verify_cvv(&cvk, PAN, "8701", "101", "561")?);
+    assert!(!verify_cvv(&cvk, PAN, "8702", "101", "561")?);
+    Ok(())
+}
+
+#[test]
+fn test_variants() -> Result<()> {
+    let cvk = TdesKey::from_hex_str(CVK)?;
+    assert_eq!(cvv2(&cvk, PAN, "8701")?, "636");
+    assert_eq!(cvv2(&cvk, PAN, "8701")?, cvv(&cvk, PAN, "8701", "000")?);
+    assert_eq!(icvv(&cvk, PAN, "8701")?, "651");
+    assert_eq!(dcvv(&cvk, PAN, "8701", "101", 3)?, "792");
+    Ok(())
+}
+
+#[test]
+fn test_invalid_input() -> Result<()> {
+    let cvk = TdesKey::from_hex_str(CVK)?;
+    assert!(cvv(&cvk, "41234567890", "8701", "101").is_err());
+    assert!(cvv(&cvk, PAN, "870", "101").is_err());
+    assert!(cvv(&cvk, PAN, "8701", "10A").is_err());
+    assert!(dcvv(&cvk, PAN, "8701", "101", 10000).is_err());
+
+    let cvk = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA987654321089ABCDEF01234567")?;
+    assert!(cvv(&cvk, PAN, "8701", "101").is_err());
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use des_ndtp::dukpt::{
+    derive_ipek, derive_transaction_key, derive_working_key, DukptKeyType, DukptTerminal, Ksn,
+};
+use des_ndtp::{Block, FromHexStr, Result, TdesKey, ToHexString};
+
+const BDK: &str = "0123456789ABCDEFFEDCBA9876543210";
+const KSN: &str = "FFFF9876543210E00000";
+
+#[test]
+fn test_ksn() -> Result<()> {
+    let ksn = Ksn::from_hex_str("FFFF9876543210E00012")?;
+    assert_eq!(ksn.counter(), 0x12);
+    assert_eq!(
+        ksn.with_counter(0x1F_FFFF)?.to_upper_hex(),
+        "FFFF9876543210FFFFFF"
+    );
+    assert_eq!(ksn.with_counter(0)?.to_upper_hex(), KSN);
+    assert!(ksn.with_counter(0x20_0000).is_err());
+    assert!(Ksn::from_hex_str("FFFF9876543210E000").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_ipek() -> Result<()> {
+    // ANSI X9.24-1:2009, appendix A
+    let bdk = TdesKey::from_hex_str(BDK)?;
+    let ipek = derive_ipek(&bdk, &Ksn::from_hex_str("FFFF9876543210E00008")?)?;
+    assert_eq!(ipek.to_upper_hex(), "6AC292FAA1315B4D858AB3A3D7D5933A");
+    Ok(())
+}
+
+#[test]
+fn test_transaction_key() -> Result<()> {
+    let bdk = TdesKey::from_hex_str(BDK)?;
+    for (ksn, key) in [
+        ("FFFF9876543210E00001", "042666B49184CFA368DE9628D0397BC9"),
+        ("FFFF9876543210E00002", "C46551CEF9FD24B0AA9AD834130D3BC7"),
+        ("FFFF9876543210E00003", "0DF3D9422ACA56E547676D07AD6BADFA"),
+        ("FFFF9876543210E00009", "27E31064FDC565698900E2057F658E7E"),
+        ("FFFF9876543210E00010", "59598DCBD9BD94C094165CE453585F57"),
+        ("FFFF9876543210EFFC00", "F9430DF975082491C77BE4EF4FDB91EE"),
+    ] {
+        let ksn = Ksn::from_hex_str(ksn)?;
+        assert_eq!(derive_transaction_key(&bdk, &ksn)?.to_upper_hex(), key);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_working_keys() -> Result<()> {
+    let key = TdesKey::from_hex_str("042666B49184CFA368DE9628D0397BC9")?;
+    for (key_type, expected) in [
+        (DukptKeyType::Pin, "042666B49184CF5C68DE9628D0397B36"),
+        (DukptKeyType::MacRequest, "042666B4918430A368DE9628D03984C9"),
+        (
+            DukptKeyType::MacResponse,
+            "042666B46E84CFA368DE96282F397BC9",
+        ),
+        (
+            DukptKeyType::DataRequest,
+            "448D3F076D8304036A55A3D7E0055A78",
+        ),
+    ] {
+        assert_eq!(derive_working_key(&key, key_type)?.to_upper_hex(), expected);
+    }
+
+    // PIN 1234 and PAN 4012345678909 in ISO format 0
+    let pin_key = derive_working_key(&key, DukptKeyType::Pin)?;
+    let pin_block = Block::from_bytes(&[0x04, 0x12, 0x74, 0xED, 0xCB, 0xA9, 0x87, 0x6F])?;
+    assert_eq!(
+        pin_key.encode(&pin_block)?.to_bytes(),
+        [0x1B, 0x9C, 0x18, 0x45, 0xEB, 0x99, 0x3A, 0x7A]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_terminal() -> Result<()> {
+    let bdk = TdesKey::from_hex_str(BDK)?;
+    let ksn = Ksn::from_hex_str(KSN)?;
+    let mut terminal = DukptTerminal::new(&derive_ipek(&bdk, &ksn)?, &ksn)?;
+    for counter in 1..=24 {
+        assert_eq!(terminal.ksn()?.counter(), counter);
+        let (ksn, key) = terminal.next_key()?;
+        assert_eq!(ksn.counter(), counter);
+        assert_eq!(key, derive_transaction_key(&bdk, &ksn)?);
+    }
+    assert!(!terminal.is_exhausted());
+    assert_eq!(format!("{terminal:?}"), "DukptTerminal { key: <redacted> }");
+    Ok(())
+}
+
+#[test]
+fn test_terminal_skips_counters() -> Result<()> {
+    let bdk = TdesKey::from_hex_str(BDK)?;
+    let ksn = Ksn::from_hex_str(KSN)?;
+    let mut terminal = DukptTerminal::new(&derive_ipek(&bdk, &ksn)?, &ksn)?;
+    let mut counters = Vec::new();
+    while terminal.ksn()?.counter() <= 0x800 {
+        let (ksn, key) = terminal.next_key()?;
+        if ksn.counter() >= 0x7FC {
+            assert_eq!(key, derive_transaction_key(&bdk, &ksn)?);
+        }
+        counters.push(ksn.counter());
+    }
+    assert!(counters.iter().all(|counter| counter.count_ones() <= 10));
+    // 0x7FF has 11 one bits, so 0x7FE is followed by 0x800
+    assert_eq!(counters[counters.len() - 4..], [0x7FC, 0x7FD, 0x7FE, 0x800]);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use des_ndtp::emv::{
+    derive_master_key, derive_session_key, generate_arpc_method_1, generate_arpc_method_2,
+    generate_arqc, verify_arpc_method_1, verify_arpc_method_2, verify_arqc, MasterKeyOption,
+};
+use des_ndtp::{FromHexStr, Result, TdesKey, ToHexString};
+
+const IMK: &str = "0123456789ABCDEFFEDCBA9876543210";
+const MASTER_KEY: &str = "4319AD679E731392E657B99D37046ED5";
+const SESSION_KEY: &str = "5F1A6246AEDB07B24A269CD3526DFCF6";
+const CDOL: &str = "000000001000000000000000084000000000000840250101009B28CE58005C000001A0B80000";
+
+#[test]
+fn test_master_key() -> Result<()> {
+    let imk = TdesKey::from_hex_str(IMK)?;
+    for option in [MasterKeyOption::A, MasterKeyOption::B] {
+        let key = derive_master_key(&imk, "5413339000001513", 1, option)?;
+        assert_eq!(key.to_upper_hex(), MASTER_KEY);
+        assert!(key.has_odd_parity());
+    }
+    // option B hashes PANs longer than 16 digits
+    let key = derive_master_key(&imk, "12345678901234567", 1, MasterKeyOption::B)?;
+    assert_eq!(key.to_upper_hex(), "AD406D7F6D7570916D75E5DCAB8CF737");
+    let key = derive_master_key(&imk, "12345678901234567", 1, MasterKeyOption::A)?;
+    assert_eq!(
+        key,
+        derive_master_key(&imk, "2345678901234567", 1, MasterKeyOption::A)?
+    );
+
+    assert!(derive_master_key(&imk, "541333900000151X", 1, MasterKeyOption::A).is_err());
+    assert!(derive_master_key(&imk, "5413339000001513", 100, MasterKeyOption::A).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_session_key() -> Result<()> {
+    let master_key = TdesKey::from_hex_str(MASTER_KEY)?;
+    let session_key = derive_session_key(&master_key, 0x0001)?;
+    assert_eq!(session_key.to_upper_hex(), SESSION_KEY);
+    assert_ne!(derive_session_key(&master_key, 0x0002)?, session_key);
+    Ok(())
+}
+
+#[test]
+fn test_arqc() -> Result<()> {
+    let session_key = TdesKey::from_hex_str(SESSION_KEY)?;
+    let arqc = generate_arqc(&session_key, &Vec::from_hex_str(CDOL)?)?;
+    assert_eq!(arqc.to_vec(), Vec::from_hex_str("544AD50E006FBFB9")?);
+    assert!(verify_arqc(&session_key, &Vec::from_hex_str(CDOL)?, &arqc)?);
+
+    let mut data = Vec::from_hex_str(CDOL)?;
+    data[0] ^= 0x01;
+    assert!(!verify_arqc(&session_key, &data, &arqc)?);
+    Ok(())
+}
+
+#[test]
+fn test_arpc() -> Result<()> {
+    let session_key = TdesKey::from_hex_str(SESSION_KEY)?;
+    let arqc = [0x54, 0x4A, 0xD5, 0x0E, 0x00, 0x6F, 0xBF, 0xB9];
+
+    let arpc = generate_arpc_method_1(&session_key, &arqc, b"00")?;
+    assert_eq!(arpc.to_vec(), Vec::from_hex_str("1AB796C4BD120F7B")?);
+    assert!(verify_arpc_method_1(&session_key, &arqc, b"00", &arpc)?);
+    assert!(!verify_arpc_method_1(&session_key, &arqc, b"05", &arpc)?);
+
+    let csu = [0x00, 0x82, 0x00, 0x00];
+    let arpc = generate_arpc_method_2(&session_key, &arqc, &csu, &[])?;
+    assert_eq!(arpc.to_vec(), Vec::from_hex_str("692465D3")?);
+    let arpc = generate_arpc_method_2(&session_key, &arqc, &csu, &[0x01, 0x02])?;
+    assert_eq!(arpc.to_vec(), Vec::from_hex_str("AC524DA8")?);
+    assert!(verify_arpc_method_2(
+        &session_key,
+        &arqc,
+        &csu,
+        &[0x01, 0x02],
+        &arpc
+    )?);
+    assert!(!verify_arpc_method_2(
+        &session_key,
+        &arqc,
+        &csu,
+        &[],
+        &arpc
+    )?);
+    assert!(generate_arpc_method_2(&session_key, &arqc, &csu, &[0; 9]).is_err());
+    Ok(())
+}
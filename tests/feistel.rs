@@ -0,0 +1,54 @@
+use bitvec::prelude::*;
+use des_ndtp::{Feistel, Result};
+
+fn bits(s: &str) -> BitVec {
+    s.chars().map(|ch| ch == '1').collect()
+}
+
+fn subkey(round: u8) -> Result<BitVec> {
+    Ok(bits(&format!("{:08b}", round.wrapping_mul(37))))
+}
+
+fn round_fn(right: &BitVec, subkey: &BitVec) -> Result<BitVec> {
+    let mut result = right.clone() ^ subkey;
+    result.rotate_left(3);
+    Ok(result)
+}
+
+#[test]
+fn test_single_round() -> Result<()> {
+    let network = Feistel::new(
+        4,
+        1..=1,
+        |right: &BitVec, subkey: &BitVec| Ok(right.clone() ^ subkey),
+        |_| Ok(bits("1111")),
+    );
+    // left ^= f(right), halves stay in place after the only round
+    assert_eq!(network.encode(&bits("00001010"))?, bits("01011010"));
+    assert_eq!(network.decode(&bits("01011010"))?, bits("00001010"));
+    Ok(())
+}
+
+#[test]
+fn test_round_trip() -> Result<()> {
+    for rounds in [1..=1, 1..=16, 1..=32, 5..=9] {
+        let network = Feistel::new(8, rounds, round_fn, subkey);
+        for data in ["0000000000000000", "1010110011110000", "1111111111111111"] {
+            let data = bits(data);
+            let encoded = network.encode(&data)?;
+            assert_eq!(network.decode(&encoded)?, data);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_invalid_lengths() {
+    let network = Feistel::new(8, 1..=4, round_fn, subkey);
+    assert_eq!(network.half_width(), 8);
+    assert_eq!(network.rounds(), &(1..=4));
+    assert!(network.encode(&bits("101")).is_err());
+
+    let network = Feistel::new(8, 1..=4, |_: &BitVec, _: &BitVec| Ok(bits("1")), subkey);
+    assert!(network.encode(&bits("1010110011110000")).is_err());
+}
@@ -0,0 +1,161 @@
+use des_ndtp::modes::{cfb_decode, cfb_encode, ecb_decode, ecb_encode};
+use des_ndtp::{Block, Error, FromHexStr, Gost28147, GostByteOrder, GostSBox, Result, ToHexString};
+
+const KEY: &str = "FFEEDDCCBBAA99887766554433221100F0F1F2F3F4F5F6F7F8F9FAFBFCFDFEFF";
+
+#[test]
+fn test_encode() -> Result<()> {
+    // GOST R 34.12-2015, appendix A.2
+    let cipher = Gost28147::from_hex_str(KEY)?;
+    let block = Block::from_hex_str("FEDCBA9876543210")?;
+    let encoded = cipher.encode(&block)?;
+    assert_eq!(encoded.to_upper_hex(), "4EE901E5C2D8CA3D");
+    assert_eq!(cipher.decode(&encoded)?, block);
+    Ok(())
+}
+
+#[test]
+fn test_ecb() -> Result<()> {
+    // GOST R 34.13-2015, appendix A.2.1
+    let cipher = Gost28147::from_hex_str(KEY)?;
+    let plain_text =
+        Vec::from_hex_str("92DEF06B3C130A59DB54C704F8189D204A98FB2E67A8024C8912409B17B57E41")?;
+    let cipher_text = ecb_encode(&cipher, &plain_text)?;
+    assert_eq!(
+        cipher_text,
+        Vec::from_hex_str("2B073F0494F372A0DE70E715D3556E4811D8D9E9EACFBC1E7C68260996C67EFB")?
+    );
+    assert_eq!(ecb_decode(&cipher, &cipher_text)?, plain_text);
+    assert!(matches!(
+        ecb_encode(&cipher, &plain_text[..7]),
+        Err(Error::InvalidIterableLength {
+            expected: 64,
+            got: 56
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_s_boxes() -> Result<()> {
+    let block = Block::from_hex_str("FEDCBA9876543210")?;
+    let mut encoded = Vec::new();
+    for s_box in [
+        GostSBox::Tc26Z,
+        GostSBox::CryptoProA,
+        GostSBox::CryptoProB,
+        GostSBox::CryptoProC,
+        GostSBox::CryptoProD,
+        GostSBox::Test,
+    ] {
+        for row in s_box.as_table() {
+            let mut sorted = *row;
+            sorted.sort_unstable();
+            assert_eq!(sorted, core::array::from_fn(|i| i as u8));
+        }
+        let cipher = Gost28147::from_hex_str(KEY)?.with_s_box(s_box);
+        let cipher_text = cipher.encode(&block)?;
+        assert_eq!(cipher.decode(&cipher_text)?, block);
+        assert!(!encoded.contains(&cipher_text));
+        encoded.push(cipher_text);
+    }
+
+    let custom = GostSBox::Custom(*GostSBox::Tc26Z.as_table());
+    let cipher = Gost28147::from_hex_str(KEY)?.with_s_box(custom);
+    assert_eq!(cipher.encode(&block)?.to_upper_hex(), "4EE901E5C2D8CA3D");
+    Ok(())
+}
+
+#[test]
+fn test_gamma() -> Result<()> {
+    let cipher = Gost28147::from_hex_str(KEY)?.with_s_box(GostSBox::CryptoProA);
+    let iv = Block::from_hex_str("1234567890ABCDEF")?;
+    let plain_text = b"archive contents that do not fill the last block".to_vec();
+    let cipher_text = cipher.gamma(&iv, &plain_text)?;
+    assert_eq!(cipher_text.len(), plain_text.len());
+    assert_ne!(cipher_text, plain_text);
+    assert_eq!(cipher.gamma(&iv, &cipher_text)?, plain_text);
+
+    // keystream blocks differ even for equal plain text blocks
+    let cipher = Gost28147::from_hex_str(KEY)?;
+    let cipher_text = cipher.gamma(&iv, &[0; 16])?;
+    assert_ne!(cipher_text[..8], cipher_text[8..]);
+    Ok(())
+}
+
+#[test]
+fn test_cfb() -> Result<()> {
+    let cipher = Gost28147::from_hex_str(KEY)?;
+    let iv = Block::from_hex_str("1234567890ABCDEF")?;
+    let plain_text = b"archive contents that do not fill the last block".to_vec();
+    let cipher_text = cfb_encode(&cipher, &iv, &plain_text)?;
+    assert_eq!(cipher_text.len(), plain_text.len());
+    assert_eq!(
+        &cipher_text[..8],
+        &cfb_encode(&cipher, &iv, &plain_text[..8])?
+    );
+    assert_eq!(cfb_decode(&cipher, &iv, &cipher_text)?, plain_text);
+
+    // the first block is GOST R 34.13-2015 appendix A.2.5, which shifts a 128-bit
+    // register, the rest is chained with 64-bit feedback
+    let plain_text =
+        Vec::from_hex_str("92DEF06B3C130A59DB54C704F8189D204A98FB2E67A8024C8912409B17B57E41")?;
+    let cipher_text = cfb_encode(&cipher, &iv, &plain_text)?;
+    assert_eq!(
+        cipher_text,
+        Vec::from_hex_str("DB37E0E266903C83B571EE29CCA54CE791FABCB3ABBE2FE3FF5D972D770F6AE9")?
+    );
+    assert_eq!(cfb_decode(&cipher, &iv, &cipher_text)?, plain_text);
+    assert_eq!(
+        cfb_encode(&cipher, &iv, &plain_text[..13])?,
+        cipher_text[..13]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_little_endian() -> Result<()> {
+    // GOST R 34.12-2015 appendix A.2 with every key word and the block reversed
+    let cipher = Gost28147::from_hex_str(
+        "CCDDEEFF8899AABB4455667700112233F3F2F1F0F7F6F5F4FBFAF9F8FFFEFDFC",
+    )?
+    .with_byte_order(GostByteOrder::LittleEndian);
+    let block = Block::from_hex_str("1032547698BADCFE")?;
+    let encoded = cipher.encode(&block)?;
+    assert_eq!(encoded.to_upper_hex(), "3DCAD8C2E501E94E");
+    assert_eq!(cipher.decode(&encoded)?, block);
+
+    // known answers of libnettle's GOST 28147-89, which follows RFC 5830
+    let cipher = Gost28147::from_hex_str(KEY)?
+        .with_s_box(GostSBox::Test)
+        .with_byte_order(GostByteOrder::LittleEndian);
+    let iv = Block::from_hex_str("1234567890ABCDEF")?;
+    let plain_text =
+        Vec::from_hex_str("92DEF06B3C130A59DB54C704F8189D204A98FB2E67A8024C8912409B17B57E41")?;
+    let cipher_text = ecb_encode(&cipher, &plain_text)?;
+    assert_eq!(
+        cipher_text,
+        Vec::from_hex_str("D45A95EF372D4A73CBCA2F6A9E3BFE00DB6E24CF1A31DA6A6A8BEEDE6371E662")?
+    );
+    assert_eq!(ecb_decode(&cipher, &cipher_text)?, plain_text);
+
+    let cipher_text = cipher.gamma(&iv, &plain_text[..29])?;
+    assert_eq!(
+        cipher_text,
+        Vec::from_hex_str("5E9D7BD9952008EC9F0384F4F24FE71620B3160297D019F75AEAC7778B")?
+    );
+    assert_eq!(cipher.gamma(&iv, &cipher_text)?, plain_text[..29]);
+
+    let cipher_text = cfb_encode(&cipher, &iv, &plain_text)?;
+    assert_eq!(
+        cipher_text,
+        Vec::from_hex_str("56C4C7424E647A9C5E492C90AABD15D9E5F27975A88545C84C682BC23C76C213")?
+    );
+    assert_eq!(cfb_decode(&cipher, &iv, &cipher_text)?, plain_text);
+    Ok(())
+}
+
+#[test]
+fn test_invalid_key() {
+    assert!(Gost28147::from_hex_str("FFEEDDCC").is_err());
+}
@@ -0,0 +1,193 @@
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use des_ndtp::hsm::{read_frame, write_frame, Hsm, KeyType};
+use des_ndtp::modes::ecb_decode;
+use des_ndtp::pin_block::{decrypt_pin, encrypt_pin, PinBlockFormat};
+use des_ndtp::{Error, FromHexStr, Result, TdesKey, ToHexString};
+
+const LMK: &str = "0123456789ABCDEFFEDCBA987654321089ABCDEF01234567";
+const PAN: &str = "4012345678909";
+
+fn hsm() -> Result<Hsm> {
+    Ok(Hsm::new(TdesKey::from_hex_str(LMK)?))
+}
+
+/// splits response into code, error code and fields
+fn execute(hsm: &Hsm, request: &str) -> (String, String, Vec<String>) {
+    let response = String::from_utf8(hsm.execute(request.as_bytes())).unwrap();
+    let fields = match &response[4..] {
+        "" => Vec::new(),
+        fields => fields.split(';').map(str::to_string).collect(),
+    };
+    (
+        response[..2].to_string(),
+        response[2..4].to_string(),
+        fields,
+    )
+}
+
+#[test]
+fn test_generate_and_export_key() -> Result<()> {
+    let hsm = hsm()?;
+    let (code, error, fields) = execute(&hsm, "GK001;2");
+    assert_eq!((code.as_str(), error.as_str()), ("GL", "00"));
+    assert_eq!(fields[0].len(), 3 + 32);
+    assert!(fields[0].starts_with("001"));
+    let kcv = fields[1].clone();
+
+    let zmk = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    let zmk_under_lmk = hsm.import_key(KeyType::Zmk, &zmk)?;
+    let (_, error, exported) = execute(&hsm, &format!("EK{zmk_under_lmk};001;{}", fields[0]));
+    assert_eq!(error, "00");
+    assert_eq!(exported[1], kcv);
+    let key = TdesKey::from_bytes(&ecb_decode(&zmk, &Vec::from_hex_str(&exported[0])?)?)?;
+    assert_eq!(key.kcv()?, kcv);
+    assert!(key.has_odd_parity());
+
+    assert_eq!(execute(&hsm, "GK001;4").1, "15");
+    assert_eq!(execute(&hsm, "GK999;2").1, "15");
+    assert_eq!(execute(&hsm, "EK00").1, "15");
+    assert_eq!(
+        execute(&hsm, &format!("EK{zmk_under_lmk};001;0011")).1,
+        "10"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_key_types() -> Result<()> {
+    let hsm = hsm()?;
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    let zmk = hsm.import_key(KeyType::Zmk, &key)?;
+    let zpk = hsm.import_key(KeyType::Zpk, &key)?;
+    assert_eq!(&zmk[..3], "000");
+    assert_eq!(&zpk[..3], "001");
+    assert_ne!(zmk[3..], zpk[3..]);
+    // every half is encoded under its own variant
+    assert_ne!(zpk[3..19], zpk[19..]);
+
+    // the PIN key can't be exported under itself or as another type
+    assert_eq!(execute(&hsm, &format!("EK{zpk};001;{zpk}")).1, "10");
+    assert_eq!(execute(&hsm, &format!("EK{zmk};003;{zpk}")).1, "10");
+    assert_eq!(execute(&hsm, &format!("EK{zmk};001;{zpk}")).1, "00");
+    assert_eq!(execute(&hsm, &format!("GM{zpk};0102")).1, "10");
+    assert_eq!(
+        execute(&hsm, &format!("CV{zmk};4123456789012345;8701;101")).1,
+        "10"
+    );
+
+    // retagging or swapping the halves of the PIN key doesn't make a usable key
+    let retagged = format!("003{}", &zpk[3..]);
+    assert_eq!(execute(&hsm, &format!("GM{retagged};0102")).1, "10");
+    let swapped = format!("001{}{}", &zpk[19..], &zpk[3..19]);
+    assert_eq!(execute(&hsm, &format!("EK{zmk};001;{swapped}")).1, "10");
+
+    let pin_block = encrypt_pin(&key, PinBlockFormat::Iso0, "1234", PAN)?.to_upper_hex();
+    assert_eq!(
+        execute(&hsm, &format!("TP{zmk};{zpk};0;3;{pin_block};{PAN}")).1,
+        "10"
+    );
+
+    let key = TdesKey::from_hex_str("0023456789ABCDEFFEDCBA9876543210")?;
+    assert!(matches!(
+        hsm.import_key(KeyType::Zpk, &key),
+        Err(Error::InvalidParity)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_translate_pin() -> Result<()> {
+    let hsm = hsm()?;
+    let from_key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    let to_key = TdesKey::from_hex_str("FEDCBA98765432100123456789ABCDEF")?;
+    let pin_block = encrypt_pin(&from_key, PinBlockFormat::Iso0, "1234", PAN)?;
+
+    let request = format!(
+        "TP{};{};0;3;{};{PAN}",
+        hsm.import_key(KeyType::Zpk, &from_key)?,
+        hsm.import_key(KeyType::Zpk, &to_key)?,
+        pin_block.to_upper_hex()
+    );
+    let (code, error, fields) = execute(&hsm, &request);
+    assert_eq!((code.as_str(), error.as_str()), ("TQ", "00"));
+    let translated: [u8; 8] = Vec::from_hex_str(&fields[0])?.try_into().unwrap();
+    assert_eq!(
+        *decrypt_pin(&to_key, PinBlockFormat::Iso3, &translated, PAN)?,
+        "1234"
+    );
+
+    let request = request.replace(";0;3;", ";4;3;");
+    assert_eq!(execute(&hsm, &request).1, "15");
+    // PAN bound block can't leave as format 1
+    let request = request.replace(";4;3;", ";0;1;");
+    assert_eq!(execute(&hsm, &request).1, "15");
+    Ok(())
+}
+
+#[test]
+fn test_mac() -> Result<()> {
+    let hsm = hsm()?;
+    let key = hsm.import_key(
+        KeyType::Tak,
+        &TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?,
+    )?;
+    let (code, error, fields) = execute(&hsm, &format!("GM{key};0102030405060708090A"));
+    assert_eq!((code.as_str(), error.as_str()), ("GN", "00"));
+    let mac = &fields[0];
+    assert_eq!(mac.len(), 16);
+
+    let verify = |data: &str, mac: &str| execute(&hsm, &format!("VM{key};{data};{mac}"));
+    assert_eq!(
+        verify("0102030405060708090A", mac),
+        ("VN".to_string(), "00".to_string(), vec![])
+    );
+    assert_eq!(verify("0102030405060708090A", &mac.to_lowercase()).1, "00");
+    assert_eq!(verify("0102030405060708090A", &mac[..8]).1, "01");
+    assert_eq!(verify("0102030405060708090A", "XY").1, "15");
+    assert_eq!(verify("0102030405060708090B", mac).1, "01");
+    assert_eq!(verify("0102030405060708090", mac).1, "15");
+    Ok(())
+}
+
+#[test]
+fn test_cvv() -> Result<()> {
+    let hsm = hsm()?;
+    let cvk = hsm.import_key(
+        KeyType::Cvk,
+        &TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?,
+    )?;
+    let (code, error, fields) = execute(&hsm, &format!("CV{cvk};4123456789012345;8701;101"));
+    assert_eq!((code.as_str(), error.as_str()), ("CW", "00"));
+    assert_eq!(fields, vec!["561"]);
+    assert_eq!(
+        execute(&hsm, &format!("CV{cvk};4123456789012345;870;101")).1,
+        "15"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unknown_command() -> Result<()> {
+    let hsm = hsm()?;
+    assert_eq!(execute(&hsm, "XX").1, "68");
+    assert_eq!(execute(&hsm, "").0, "ZZ");
+    Ok(())
+}
+
+#[test]
+fn test_tcp() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || hsm().and_then(|hsm| hsm.serve(&listener)));
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    for _ in 0..2 {
+        write_frame(&mut stream, b"GK001;3")?;
+        let response = read_frame(&mut stream)?.unwrap();
+        assert_eq!(&response[..4], b"GL00");
+        assert_eq!(response.len(), 4 + 3 + 48 + 1 + 6);
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use des_ndtp::{FromHexStr, KcvMethod, MainKey, Result, TdesKey};
+
+#[test]
+fn test_kcv() -> Result<()> {
+    let key = MainKey::from_hex_str("0123456789ABCDEF")?;
+    assert_eq!(key.kcv()?, "D5D44F");
+    assert_eq!(key.kcv_with(KcvMethod::Legacy, 4)?, "D5D4");
+
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    assert_eq!(key.kcv()?, "08D7B4");
+    assert_eq!(key.kcv_with(KcvMethod::Legacy, 16)?, "08D7B4FB629D0885");
+    assert_eq!(key.kcv_with(KcvMethod::Cmac, 10)?, "0A82458664");
+    assert!(key.kcv_with(KcvMethod::Legacy, 17).is_err());
+    assert!(key.kcv_with(KcvMethod::Legacy, 0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_verify_kcv() -> Result<()> {
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    assert!(key.verify_kcv(KcvMethod::Legacy, "08D7B4")?);
+    assert!(key.verify_kcv(KcvMethod::Legacy, "08d7b4fb")?);
+    assert!(!key.verify_kcv(KcvMethod::Legacy, "08D7B5")?);
+    assert!(key.verify_kcv(KcvMethod::Cmac, "0A82458664")?);
+    assert!(!key.verify_kcv(KcvMethod::Cmac, "08D7B4")?);
+
+    let key = MainKey::from_hex_str("0123456789ABCDEF")?;
+    assert!(key.verify_kcv(KcvMethod::Legacy, "D5D44F")?);
+    Ok(())
+}
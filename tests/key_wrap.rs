@@ -0,0 +1,62 @@
+use des_ndtp::key_wrap::{unwrap_key, wrap_key, wrap_key_with};
+use des_ndtp::{Error, FromHexStr, Result, TdesKey, ToHexString};
+
+mod common;
+
+use common::ReplayRng;
+
+const KEK: &str = "255E0D1C07B646DFB3134CC843BA8AA71F025B7C0838251F";
+const CEK: &str = "2923BF85E06DD6AE529149F1F1BAE9EAB3A7DA3D860D3E98";
+const WRAPPED: &str =
+    "690107618EF092B3B48CA1796B234AE9FA33EBB4159604037DB5D6A84EB3AAC2768C632775A467D4";
+
+#[test]
+fn test_rfc_vector() -> Result<()> {
+    // RFC 3217 example
+    let kek = TdesKey::from_hex_str(KEK)?;
+    let cek = TdesKey::from_hex_str(CEK)?;
+    let mut rng = ReplayRng(Vec::from_hex_str("5DD4CBFC96F5453B")?);
+    assert_eq!(
+        wrap_key_with(&kek, &cek, &mut rng)?,
+        Vec::from_hex_str(WRAPPED)?
+    );
+    assert_eq!(
+        unwrap_key(&kek, &Vec::from_hex_str(WRAPPED)?)?.to_upper_hex(),
+        CEK
+    );
+    Ok(())
+}
+
+#[test]
+fn test_round_trip() -> Result<()> {
+    let kek = TdesKey::from_hex_str(KEK)?;
+    let cek = TdesKey::generate(3)?;
+    assert_eq!(unwrap_key(&kek, &wrap_key(&kek, &cek)?)?, cek);
+
+    // double-length keys come back with K3 = K1
+    let cek = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    assert_eq!(
+        unwrap_key(&kek, &wrap_key(&kek, &cek)?)?.to_upper_hex(),
+        "0123456789ABCDEFFEDCBA98765432100123456789ABCDEF"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_checksum_mismatch() -> Result<()> {
+    let kek = TdesKey::from_hex_str(KEK)?;
+    let mut wrapped = Vec::from_hex_str(WRAPPED)?;
+    wrapped[20] ^= 0x01;
+    assert!(matches!(
+        unwrap_key(&kek, &wrapped),
+        Err(Error::ChecksumMismatch)
+    ));
+
+    let other = TdesKey::from_hex_str(CEK)?;
+    assert!(matches!(
+        unwrap_key(&other, &Vec::from_hex_str(WRAPPED)?),
+        Err(Error::ChecksumMismatch)
+    ));
+    assert!(unwrap_key(&kek, &Vec::from_hex_str(WRAPPED)?[..32]).is_err());
+    Ok(())
+}
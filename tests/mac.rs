@@ -0,0 +1,201 @@
+use des_ndtp::mac::{
+    cmac, iso9797_mac1, retail_mac, verify_iso9797_mac1, verify_retail_mac, MacPadding, RetailMac,
+};
+use des_ndtp::{Error, FromHexStr, MainKey, Result, TdesKey};
+
+#[test]
+fn test_cmac() -> Result<()> {
+    // NIST SP 800-38B, appendix D.4
+    let key = TdesKey::from_hex_str("8AA83BF8CBDA10620BC1BF19FBB6CD58BC313D4A371CA8B5")?;
+    assert_eq!(
+        cmac(&key, &[])?.to_vec(),
+        Vec::from_hex_str("B7A688E122FFAF95")?
+    );
+    assert_eq!(
+        cmac(
+            &key,
+            &Vec::from_hex_str("6BC1BEE22E409F96E93D7E117393172A")?
+        )?
+        .to_vec(),
+        Vec::from_hex_str("286D394673448197")?
+    );
+    let key = TdesKey::from_hex_str("4CF15134A2850DD58A3D10BA80570D38")?;
+    assert_eq!(
+        cmac(
+            &key,
+            &Vec::from_hex_str("6BC1BEE22E409F96E93D7E117393172AAE2D8A57")?
+        )?
+        .to_vec(),
+        Vec::from_hex_str("62DD1B471902BD4E")?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_padding() -> Result<()> {
+    assert_eq!(
+        MacPadding::Method1.pad(&[]),
+        Vec::from_hex_str("0000000000000000")?
+    );
+    assert_eq!(
+        MacPadding::Method1.pad(&Vec::from_hex_str("0102")?),
+        Vec::from_hex_str("0102000000000000")?
+    );
+    assert_eq!(
+        MacPadding::Method2.pad(&Vec::from_hex_str("0102030405060708")?),
+        Vec::from_hex_str("01020304050607088000000000000000")?
+    );
+    assert_eq!(
+        MacPadding::Method3.pad(&Vec::from_hex_str("010203")?),
+        Vec::from_hex_str("00000000000000180102030000000000")?
+    );
+    assert_eq!(
+        MacPadding::Method3.pad(&[]),
+        Vec::from_hex_str("0000000000000000")?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_iso9797_mac1() -> Result<()> {
+    // FIPS 113 example, the first 32 bits are F1D30F68
+    let key = MainKey::from_hex_str("0123456789ABCDEF")?;
+    let data = b"7654321 Now is the time for ";
+    assert_eq!(
+        iso9797_mac1(&key, data, MacPadding::Method1, 8)?,
+        Vec::from_hex_str("F1D30F6849312CA4")?
+    );
+    assert_eq!(
+        iso9797_mac1(&key, data, MacPadding::Method1, 4)?,
+        Vec::from_hex_str("F1D30F68")?
+    );
+    assert_eq!(
+        iso9797_mac1(&key, data, MacPadding::Method2, 8)?,
+        Vec::from_hex_str("D0163999B2406DED")?
+    );
+    assert_eq!(
+        iso9797_mac1(&key, data, MacPadding::Method3, 6)?,
+        Vec::from_hex_str("25F09C837BA1")?
+    );
+    assert!(matches!(
+        iso9797_mac1(&key, data, MacPadding::Method1, 9),
+        Err(Error::InvalidMacLength(9))
+    ));
+
+    assert!(verify_iso9797_mac1(
+        &key,
+        data,
+        MacPadding::Method1,
+        &Vec::from_hex_str("F1D30F68")?
+    )?);
+    assert!(!verify_iso9797_mac1(
+        &key,
+        data,
+        MacPadding::Method2,
+        &Vec::from_hex_str("F1D30F68")?
+    )?);
+    assert!(
+        verify_iso9797_mac1(&key, data, MacPadding::Method1, &Vec::from_hex_str("F1D3")?).is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_retail_mac() -> Result<()> {
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    let data = b"7654321 Now is the time for ";
+    assert_eq!(
+        retail_mac(&key, data, MacPadding::Method1, 8)?,
+        Vec::from_hex_str("AE4B45B1B527642F")?
+    );
+    assert_eq!(
+        retail_mac(&key, data, MacPadding::Method2, 8)?,
+        Vec::from_hex_str("863BE25DAF06098B")?
+    );
+    assert_eq!(
+        retail_mac(&key, data, MacPadding::Method3, 4)?,
+        Vec::from_hex_str("593578D9")?
+    );
+    assert!(verify_retail_mac(
+        &key,
+        data,
+        MacPadding::Method2,
+        &Vec::from_hex_str("863BE25DAF06")?
+    )?);
+    assert!(!verify_retail_mac(
+        &key,
+        data,
+        MacPadding::Method1,
+        &Vec::from_hex_str("863BE25DAF06")?
+    )?);
+
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA987654321089ABCDEF01234567")?;
+    assert!(matches!(
+        retail_mac(&key, data, MacPadding::Method1, 8),
+        Err(Error::InvalidKeyLength(192))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_retail_mac_incremental() -> Result<()> {
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    let data = b"7654321 Now is the time for ";
+    for padding in [
+        MacPadding::Method1,
+        MacPadding::Method2,
+        MacPadding::Method3,
+    ] {
+        for split in [0, 3, 8, 16, 27, 28] {
+            let mut mac = RetailMac::new(&key, padding, 8, Some(data.len()))?;
+            mac.update(&data[..split])?;
+            mac.update(&data[split..])?;
+            assert_eq!(mac.finalize()?, retail_mac(&key, data, padding, 8)?);
+        }
+        let mac = RetailMac::new(&key, padding, 8, Some(0))?;
+        assert_eq!(mac.finalize()?, retail_mac(&key, &[], padding, 8)?);
+    }
+
+    let mut mac = RetailMac::new(&key, MacPadding::Method1, 8, None)?;
+    mac.update(&data[..16])?;
+    assert_eq!(
+        mac.clone().finalize()?,
+        retail_mac(&key, &data[..16], MacPadding::Method1, 8)?
+    );
+    mac.update(&data[16..])?;
+    assert!(mac.verify(&Vec::from_hex_str("AE4B45B1B527642F")?)?);
+    assert_eq!(
+        format!("{:?}", RetailMac::new(&key, MacPadding::Method1, 8, None)?),
+        "RetailMac { key: <redacted> }"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_retail_mac_data_length() -> Result<()> {
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    let data = b"7654321 Now is the time for ";
+    assert!(matches!(
+        RetailMac::new(&key, MacPadding::Method3, 8, None),
+        Err(Error::MissingDataLength)
+    ));
+
+    let mut mac = RetailMac::new(&key, MacPadding::Method3, 4, Some(data.len()))?;
+    for chunk in data.chunks(5) {
+        mac.update(chunk)?;
+    }
+    assert!(mac.verify(&Vec::from_hex_str("593578D9")?)?);
+
+    let mut mac = RetailMac::new(&key, MacPadding::Method3, 8, Some(8))?;
+    assert!(matches!(
+        mac.update(&data[..9]),
+        Err(Error::InvalidIterableLength {
+            expected: 64,
+            got: 72
+        })
+    ));
+    let mut mac = RetailMac::new(&key, MacPadding::Method2, 8, Some(data.len()))?;
+    mac.update(&data[..27])?;
+    assert!(mac.finalize().is_err());
+    Ok(())
+}
@@ -1,7 +1,11 @@
 use bitvec::prelude::*;
-use des_ndtp::{FromHexStr, MainKey, Result, ShiftDirection, ToHexString};
+use des_ndtp::{FromHexStr, MainKey, Result, ShiftDirection, ToHexString, WeakKeyPolicy};
 use std::str::FromStr;
 
+mod common;
+
+use common::ReplayRng;
+
 #[test]
 fn test_from_string() -> Result<()> {
     let key = MainKey::new(BitVec::from(
@@ -99,3 +103,73 @@ fn test_round_key() -> Result<()> {
     assert_eq!(key, MainKey::from_hex_str("181C5D75C66D")?);
     Ok(())
 }
+
+#[test]
+fn test_parity() -> Result<()> {
+    let key = MainKey::from_hex_str("0123456789ABCDEF")?;
+    assert!(key.has_odd_parity());
+    assert_eq!(key.with_odd_parity(), key);
+
+    let key = MainKey::from_hex_str("0022446688AACCEE")?;
+    assert!(!key.has_odd_parity());
+    let adjusted = key.with_odd_parity();
+    assert!(adjusted.has_odd_parity());
+    assert_eq!(adjusted.to_upper_hex(), "0123456789ABCDEF");
+
+    // parity bits don't take part in encryption
+    let block = des_ndtp::Block::from_hex_str("8787878787878787")?;
+    assert_eq!(block.encode(&key)?, block.encode(&adjusted)?);
+    Ok(())
+}
+
+#[test]
+fn test_new_strict() -> Result<()> {
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    assert!(MainKey::new_strict(key.into_bitvec()).is_ok());
+
+    let key = MainKey::from_hex_str("133457799BBCDFF0")?;
+    assert!(MainKey::new_strict(key.into_bitvec()).is_err());
+
+    let key = MainKey::from_str("0000001")?;
+    assert!(MainKey::new_strict(key.into_bitvec()).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_bytes() -> Result<()> {
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    let bytes = [0x13, 0x34, 0x57, 0x79, 0x9B, 0xBC, 0xDF, 0xF1];
+    assert_eq!(key.to_bytes(), bytes);
+    assert_eq!(MainKey::from_bytes(&bytes), key);
+    Ok(())
+}
+
+#[test]
+fn test_generate() -> Result<()> {
+    let key = MainKey::generate()?;
+    assert!(key.has_odd_parity());
+    assert_eq!(key.as_bitvec().len(), 64);
+    assert_eq!(key.weakness(), None);
+    assert_ne!(key, MainKey::generate()?);
+    Ok(())
+}
+
+#[test]
+fn test_generate_skips_weak_keys() -> Result<()> {
+    let mut bytes = vec![0x00; 8];
+    bytes.extend([0x1F, 0x1F, 0x01, 0x01, 0x0E, 0x0E, 0x01, 0x01]);
+    bytes.extend([0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0]);
+
+    let key = MainKey::generate_with(&mut ReplayRng(bytes.clone()), WeakKeyPolicy::RejectWeak)?;
+    assert_eq!(key.to_upper_hex(), "1F1F01010E0E0101");
+
+    let key = MainKey::generate_with(
+        &mut ReplayRng(bytes.clone()),
+        WeakKeyPolicy::RejectPossiblyWeak,
+    )?;
+    assert_eq!(key.to_upper_hex(), "133457799BBCDFF1");
+
+    let key = MainKey::generate_with(&mut ReplayRng(bytes), WeakKeyPolicy::AllowAll)?;
+    assert_eq!(key.to_upper_hex(), "0101010101010101");
+    Ok(())
+}
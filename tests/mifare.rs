@@ -0,0 +1,115 @@
+use des_ndtp::mifare::{
+    receive_mode_cbc, send_mode_cbc, DesfireCard, DesfireReader, UltralightCard, UltralightReader,
+};
+use des_ndtp::{Error, FromHexStr, Result, TdesKey, ToHexString};
+
+mod common;
+
+use common::ReplayRng;
+
+const KEY: &str = "0123456789ABCDEFFEDCBA9876543210";
+const RND_A: &str = "A8AF3B256C75ED40";
+const RND_B: &str = "51E764602678DF2B";
+
+#[test]
+fn test_cbc_modes() -> Result<()> {
+    let key = TdesKey::from_hex_str(KEY)?;
+    let data: Vec<u8> = (0..16).collect();
+    let sent = send_mode_cbc(&key, &data)?;
+    assert_eq!(sent, Vec::from_hex_str("59A92BB0B5F628B963F799A37E0B37C3")?);
+    assert!(send_mode_cbc(&key, &data[..15]).is_err());
+
+    // the card answers in plain CBC mode with zero IV, which the reader decodes
+    let response = Vec::from_hex_str("52C5C0705D9089E1FD2F0CF61DD3BC0A")?;
+    assert_eq!(receive_mode_cbc(&key, &response)?, data);
+    assert!(receive_mode_cbc(&key, &response[..12]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_desfire_authentication() -> Result<()> {
+    for (key, session_key) in [
+        (KEY, "A8AF3B2551E76460 6C75ED402678DF2B"),
+        (
+            "0123456789ABCDEF0123456789ABCDEF",
+            "A8AF3B2551E76460 A8AF3B2551E76460",
+        ),
+    ] {
+        let key = TdesKey::from_hex_str(key)?;
+        let mut card = DesfireCard::new(key.clone());
+        let mut reader = DesfireReader::new(key);
+
+        let challenge =
+            card.begin_authentication_with(&mut ReplayRng(Vec::from_hex_str(RND_B)?))?;
+        let response = reader
+            .respond_to_challenge_with(&challenge, &mut ReplayRng(Vec::from_hex_str(RND_A)?))?;
+        let confirmation = card.complete_authentication(&response)?;
+        let reader_key = reader.verify_card(&confirmation)?;
+
+        assert_eq!(reader_key.to_upper_hex(), session_key.replace(' ', ""));
+        assert_eq!(card.session_key(), Some(&reader_key));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_desfire_wrong_key() -> Result<()> {
+    let mut card = DesfireCard::new(TdesKey::from_hex_str(KEY)?);
+    let mut reader = DesfireReader::new(TdesKey::from_hex_str("0123456789ABCDEF0123456789ABCDEF")?);
+
+    let challenge = card.begin_authentication()?;
+    let response = reader.respond_to_challenge(&challenge)?;
+    assert!(matches!(
+        card.complete_authentication(&response),
+        Err(Error::AuthenticationFailed)
+    ));
+    assert!(card.session_key().is_none());
+    // the exchange can't be replayed
+    assert!(card.complete_authentication(&response).is_err());
+
+    let mut card = DesfireCard::new(TdesKey::from_hex_str(KEY)?);
+    let mut reader = DesfireReader::new(TdesKey::from_hex_str(KEY)?);
+    let challenge = card.begin_authentication()?;
+    reader.respond_to_challenge(&challenge)?;
+    assert!(reader.verify_card(&challenge).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_ultralight_authentication() -> Result<()> {
+    // MIFARE Ultralight C datasheet example, key "IEMKAERB!NACUOYF"
+    let key = TdesKey::from_hex_str("49454D4B41455242214E4143554F5946")?;
+    let mut card = UltralightCard::new(key.clone());
+    let mut reader = UltralightReader::new(key);
+
+    let challenge = card.begin_authentication_with(&mut ReplayRng(Vec::from_hex_str(RND_B)?))?;
+    assert_eq!(challenge.to_vec(), Vec::from_hex_str("577293FD2F34CA51")?);
+    let response =
+        reader.respond_to_challenge_with(&challenge, &mut ReplayRng(Vec::from_hex_str(RND_A)?))?;
+    assert_eq!(
+        response,
+        Vec::from_hex_str("0A638559FC7737F9F15D7862EBBE967A")?
+    );
+    let confirmation = card.complete_authentication(&response)?;
+    assert_eq!(
+        confirmation.to_vec(),
+        Vec::from_hex_str("3B884FA07C137CE1")?
+    );
+    reader.verify_card(&confirmation)?;
+    Ok(())
+}
+
+#[test]
+fn test_ultralight_wrong_key() -> Result<()> {
+    let mut card = UltralightCard::new(TdesKey::from_hex_str("49454D4B41455242214E4143554F5946")?);
+    let mut reader = UltralightReader::new(TdesKey::from_hex_str(KEY)?);
+
+    let challenge = card.begin_authentication()?;
+    let response = reader.respond_to_challenge(&challenge)?;
+    assert!(matches!(
+        card.complete_authentication(&response),
+        Err(Error::AuthenticationFailed)
+    ));
+    assert!(reader.verify_card(&[0; 8]).is_err());
+    Ok(())
+}
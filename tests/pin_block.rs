@@ -0,0 +1,166 @@
+use des_ndtp::pin_block::{
+    build_pin_block, build_pin_block_with, decrypt_pin, encrypt_pin, parse_pin_block,
+    translate_pin_block, PinBlockFormat,
+};
+use des_ndtp::{Block, Error, FromHexStr, MainKey, Result, TdesKey};
+
+mod common;
+
+use common::ReplayRng;
+
+const PAN: &str = "4012345678909";
+
+#[test]
+fn test_build() -> Result<()> {
+    assert_eq!(
+        build_pin_block(PinBlockFormat::Iso0, "1234", PAN)?,
+        Block::from_hex_str("041274EDCBA9876F")?.to_bytes()
+    );
+    assert_eq!(
+        build_pin_block(PinBlockFormat::Iso2, "12345", PAN)?,
+        Block::from_hex_str("2512345FFFFFFFFF")?.to_bytes()
+    );
+    assert_eq!(
+        build_pin_block_with(
+            PinBlockFormat::Iso1,
+            "1234",
+            PAN,
+            &mut ReplayRng(vec![0x0B])
+        )?,
+        Block::from_hex_str("141234BBBBBBBBBB")?.to_bytes()
+    );
+    assert_eq!(
+        build_pin_block_with(
+            PinBlockFormat::Iso3,
+            "1234",
+            PAN,
+            &mut ReplayRng(vec![0x02])
+        )?,
+        Block::from_hex_str("341274DEF89AB45C")?.to_bytes()
+    );
+
+    assert!(build_pin_block(PinBlockFormat::Iso0, "123", PAN).is_err());
+    assert!(build_pin_block(PinBlockFormat::Iso0, "1234567890123", PAN).is_err());
+    assert!(build_pin_block(PinBlockFormat::Iso0, "12a4", PAN).is_err());
+    assert!(build_pin_block(PinBlockFormat::Iso3, "1234", "40123456789O9").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_parse() -> Result<()> {
+    for format in [
+        PinBlockFormat::Iso0,
+        PinBlockFormat::Iso1,
+        PinBlockFormat::Iso2,
+        PinBlockFormat::Iso3,
+    ] {
+        for pin in ["1234", "987654321012"] {
+            let block = build_pin_block(format, pin, PAN)?;
+            assert_eq!(*parse_pin_block(format, &block, PAN)?, pin);
+        }
+    }
+
+    let parse = |format, block| -> Result<_> {
+        parse_pin_block(format, &Block::from_hex_str(block)?.to_bytes(), PAN)
+    };
+    // fill digits
+    assert!(matches!(
+        parse(PinBlockFormat::Iso2, "241234FFFFFFFFFE"),
+        Err(Error::InvalidPinBlock(_))
+    ));
+    assert!(parse(PinBlockFormat::Iso3, "341274EDCBA9876F").is_ok());
+    assert!(parse(PinBlockFormat::Iso3, "3412741DCBA9876F").is_err());
+    // control field, PIN length and digits
+    assert!(parse(PinBlockFormat::Iso0, "241234FFFFFFFFFF").is_err());
+    assert!(parse(PinBlockFormat::Iso2, "231234FFFFFFFFFF").is_err());
+    assert!(parse(PinBlockFormat::Iso2, "2D1234FFFFFFFFFF").is_err());
+    assert!(parse(PinBlockFormat::Iso2, "24123AFFFFFFFFFF").is_err());
+    // another PAN breaks the fill
+    assert!(parse_pin_block(
+        PinBlockFormat::Iso0,
+        &Block::from_hex_str("041274EDCBA9876F")?.to_bytes(),
+        "5012345678919"
+    )
+    .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_encrypt() -> Result<()> {
+    // ANSI X9.24-1:2009 DUKPT PIN key for the first transaction
+    let key = TdesKey::from_hex_str("042666B49184CF5C68DE9628D0397B36")?;
+    let encrypted = encrypt_pin(&key, PinBlockFormat::Iso0, "1234", PAN)?;
+    assert_eq!(
+        encrypted,
+        Block::from_hex_str("1B9C1845EB993A7A")?.to_bytes()
+    );
+    assert_eq!(
+        *decrypt_pin(&key, PinBlockFormat::Iso0, &encrypted, PAN)?,
+        "1234"
+    );
+
+    let key = MainKey::from_hex_str("0123456789ABCDEF")?;
+    let encrypted = encrypt_pin(&key, PinBlockFormat::Iso3, "5678", PAN)?;
+    assert_eq!(
+        *decrypt_pin(&key, PinBlockFormat::Iso3, &encrypted, PAN)?,
+        "5678"
+    );
+    assert!(decrypt_pin(&key, PinBlockFormat::Iso0, &encrypted, PAN).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_translate() -> Result<()> {
+    let from_key = MainKey::from_hex_str("0123456789ABCDEF")?;
+    let to_key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    let encrypted = encrypt_pin(&from_key, PinBlockFormat::Iso0, "4321", PAN)?;
+
+    for to_format in [PinBlockFormat::Iso0, PinBlockFormat::Iso3] {
+        let translated = translate_pin_block(
+            &from_key,
+            PinBlockFormat::Iso0,
+            &to_key,
+            to_format,
+            &encrypted,
+            PAN,
+        )?;
+        assert_eq!(*decrypt_pin(&to_key, to_format, &translated, PAN)?, "4321");
+    }
+    assert!(translate_pin_block(
+        &to_key,
+        PinBlockFormat::Iso0,
+        &from_key,
+        PinBlockFormat::Iso3,
+        &encrypted,
+        PAN,
+    )
+    .is_err());
+    for to_format in [PinBlockFormat::Iso1, PinBlockFormat::Iso2] {
+        assert!(matches!(
+            translate_pin_block(
+                &from_key,
+                PinBlockFormat::Iso0,
+                &to_key,
+                to_format,
+                &encrypted,
+                PAN,
+            ),
+            Err(Error::InvalidPinBlock(_))
+        ));
+    }
+
+    let encrypted = encrypt_pin(&from_key, PinBlockFormat::Iso1, "4321", PAN)?;
+    let translated = translate_pin_block(
+        &from_key,
+        PinBlockFormat::Iso1,
+        &to_key,
+        PinBlockFormat::Iso0,
+        &encrypted,
+        PAN,
+    )?;
+    assert_eq!(
+        *decrypt_pin(&to_key, PinBlockFormat::Iso0, &translated, PAN)?,
+        "4321"
+    );
+    Ok(())
+}
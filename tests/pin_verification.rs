@@ -0,0 +1,82 @@
+use des_ndtp::pin_verification::{
+    ibm3624_natural_pin, ibm3624_offset, verify_ibm3624_offset, verify_visa_pvv, visa_pvv,
+    DecimalizationTable,
+};
+use des_ndtp::{FromHexStr, MainKey, Result, TdesKey};
+
+const VALIDATION_DATA: &str = "4012345678909FFF";
+const PAN: &str = "4012345678909";
+
+#[test]
+fn test_decimalization_table() -> Result<()> {
+    assert_eq!(
+        "0123456789012345".parse::<DecimalizationTable>()?,
+        DecimalizationTable::default()
+    );
+    assert!("012345678901234".parse::<DecimalizationTable>().is_err());
+    assert!("012345678901234A".parse::<DecimalizationTable>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_ibm3624() -> Result<()> {
+    let pvk = MainKey::from_hex_str("0123456789ABCDEF")?;
+    let table = DecimalizationTable::default();
+    assert_eq!(
+        *ibm3624_natural_pin(&pvk, VALIDATION_DATA, &table, 4)?,
+        "4414"
+    );
+    assert_eq!(
+        ibm3624_offset(&pvk, VALIDATION_DATA, &table, "1234")?,
+        "7820"
+    );
+    assert_eq!(
+        ibm3624_offset(&pvk, VALIDATION_DATA, &table, "123456")?,
+        "782032"
+    );
+    // natural PIN has zero offset
+    assert_eq!(
+        ibm3624_offset(&pvk, VALIDATION_DATA, &table, "4414")?,
+        "0000"
+    );
+
+    let table = "8351296477461538".parse()?;
+    assert_eq!(
+        ibm3624_offset(&pvk, VALIDATION_DATA, &table, "1234")?,
+        "9901"
+    );
+    assert!(verify_ibm3624_offset(
+        &pvk,
+        VALIDATION_DATA,
+        &table,
+        "1234",
+        "9901"
+    )?);
+    assert!(!verify_ibm3624_offset(
+        &pvk,
+        VALIDATION_DATA,
+        &table,
+        "1235",
+        "9901"
+    )?);
+
+    assert!(ibm3624_natural_pin(&pvk, VALIDATION_DATA, &table, 3).is_err());
+    assert!(ibm3624_offset(&pvk, "4012345678909", &table, "1234").is_err());
+    assert!(ibm3624_offset(&pvk, VALIDATION_DATA, &table, "12a4").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_visa_pvv() -> Result<()> {
+    let pvk = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    assert_eq!(visa_pvv(&pvk, PAN, 1, "1234")?, "5111");
+    assert_eq!(visa_pvv(&pvk, "4123456789012345", 1, "1234")?, "1894");
+    // encoded TSP 9DAED6AEDEDCEDF1 has only 3 decimal digits
+    assert_eq!(visa_pvv(&pvk, PAN, 1, "5847")?, "9613");
+
+    assert!(verify_visa_pvv(&pvk, PAN, 1, "1234", "5111")?);
+    assert!(!verify_visa_pvv(&pvk, PAN, 2, "1234", "5111")?);
+    assert!(visa_pvv(&pvk, PAN, 10, "1234").is_err());
+    assert!(visa_pvv(&pvk, "40123456", 1, "1234").is_err());
+    Ok(())
+}
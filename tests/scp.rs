@@ -0,0 +1,176 @@
+use des_ndtp::scp::{
+    card_cryptogram, derive_scp01_keys, derive_scp02_keys, host_cryptogram, verify_card_cryptogram,
+    ScpVersion, SecureChannel, SecurityLevel, StaticKeys,
+};
+use des_ndtp::{Error, FromHexStr, Result, TdesKey, ToHexString};
+
+fn static_keys() -> Result<StaticKeys> {
+    let key = TdesKey::from_hex_str("404142434445464748494A4B4C4D4E4F")?;
+    Ok(StaticKeys {
+        enc: key.clone(),
+        mac: key.clone(),
+        dek: key,
+    })
+}
+
+const HOST_CHALLENGE: [u8; 8] = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+const SCP01_CARD_CHALLENGE: [u8; 8] = [0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18];
+/// sequence counter 000D and card challenge
+const SCP02_CARD_CHALLENGE: [u8; 8] = [0x00, 0x0D, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF];
+
+#[test]
+fn test_scp01_keys() -> Result<()> {
+    let keys = derive_scp01_keys(&static_keys()?, &HOST_CHALLENGE, &SCP01_CARD_CHALLENGE)?;
+    assert_eq!(keys.enc.to_upper_hex(), "5DCE938392D360E73D172A540E65F627");
+    assert_eq!(keys.c_mac, keys.enc);
+    assert_eq!(keys.dek, static_keys()?.dek);
+    assert!(keys.r_mac.is_none());
+
+    let cryptogram = card_cryptogram(&keys, &HOST_CHALLENGE, &SCP01_CARD_CHALLENGE)?;
+    assert_eq!(cryptogram.to_vec(), Vec::from_hex_str("0B3B7219CD88311D")?);
+    assert!(verify_card_cryptogram(
+        &keys,
+        &HOST_CHALLENGE,
+        &SCP01_CARD_CHALLENGE,
+        &cryptogram
+    )?);
+    assert_eq!(
+        host_cryptogram(&keys, &HOST_CHALLENGE, &SCP01_CARD_CHALLENGE)?.to_vec(),
+        Vec::from_hex_str("D8347BB6939D0B3A")?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_scp01_wrap() -> Result<()> {
+    let keys = derive_scp01_keys(&static_keys()?, &HOST_CHALLENGE, &SCP01_CARD_CHALLENGE)?;
+    let mut channel = SecureChannel::new(ScpVersion::Scp01, keys.clone(), SecurityLevel::CMac);
+    assert_eq!(
+        channel.wrap(&Vec::from_hex_str("80F28000024F00")?)?,
+        Vec::from_hex_str("84F280000A4F00C69E33344A7CD34D")?
+    );
+
+    // with ICV encryption the second C-MAC is chained from the encoded first one
+    let cryptogram = host_cryptogram(&keys, &HOST_CHALLENGE, &SCP01_CARD_CHALLENGE)?;
+    let mut channel = SecureChannel::new(ScpVersion::Scp01, keys.clone(), SecurityLevel::CMac)
+        .with_icv_encryption();
+    assert_eq!(
+        channel.external_authenticate(&cryptogram)?,
+        Vec::from_hex_str("8482010010D8347BB6939D0B3ABC191FF2FEF8BC17")?
+    );
+    assert_eq!(
+        channel.wrap(&Vec::from_hex_str("80F28000024F00")?)?,
+        Vec::from_hex_str("84F280000A4F007780FB57F556DE20")?
+    );
+
+    let mut channel = SecureChannel::new(ScpVersion::Scp01, keys, SecurityLevel::CMacAndEncryption);
+    // 7 bytes with length prefix make a whole block and aren't padded
+    assert_eq!(
+        channel.wrap(&Vec::from_hex_str("80E280000700010203040506")?)?,
+        Vec::from_hex_str("84E2800010E1AD0B9A718EE9F74CFDC76C6788D0A0")?
+    );
+    // C-MAC is chained from the previous command
+    assert_eq!(
+        channel.wrap(&Vec::from_hex_str("80E280000A00010203040506070809")?)?,
+        Vec::from_hex_str("84E2800018C695E97F4B605775DFE9A94989EC46E9500DEC91551CFE0B")?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_scp02_keys() -> Result<()> {
+    let keys = derive_scp02_keys(&static_keys()?, &[0x00, 0x0D])?;
+    assert_eq!(keys.enc.to_upper_hex(), "217ABF8CC47294B2411871F381D7534E");
+    assert_eq!(
+        keys.c_mac.to_upper_hex(),
+        "07EFCCEB0BB0CC01A22E0CE1E1E395F8"
+    );
+    assert_eq!(
+        keys.r_mac.as_ref().map(TdesKey::to_upper_hex),
+        Some("1AC383888CDEAF2F8EF67E16F815ACCB".to_string())
+    );
+    assert_eq!(keys.dek.to_upper_hex(), "BFF2832F9AB9DB03AD37B4E7E141C507");
+
+    assert_eq!(
+        card_cryptogram(&keys, &HOST_CHALLENGE, &SCP02_CARD_CHALLENGE)?.to_vec(),
+        Vec::from_hex_str("27D76A767C95D867")?
+    );
+    let cryptogram = host_cryptogram(&keys, &HOST_CHALLENGE, &SCP02_CARD_CHALLENGE)?;
+    assert_eq!(cryptogram.to_vec(), Vec::from_hex_str("AD10AA7691B724A8")?);
+    assert!(!verify_card_cryptogram(
+        &keys,
+        &HOST_CHALLENGE,
+        &SCP02_CARD_CHALLENGE,
+        &cryptogram
+    )?);
+    Ok(())
+}
+
+#[test]
+fn test_scp02_wrap() -> Result<()> {
+    let keys = derive_scp02_keys(&static_keys()?, &[0x00, 0x0D])?;
+    let cryptogram = host_cryptogram(&keys, &HOST_CHALLENGE, &SCP02_CARD_CHALLENGE)?;
+
+    let mut channel = SecureChannel::new(ScpVersion::Scp02, keys.clone(), SecurityLevel::CMac)
+        .with_icv_encryption();
+    assert_eq!(
+        channel.external_authenticate(&cryptogram)?,
+        Vec::from_hex_str("8482010010AD10AA7691B724A8B3A4EFB71BDB4F2A")?
+    );
+    assert_eq!(
+        channel.wrap(&Vec::from_hex_str("80F28000024F0000")?)?,
+        Vec::from_hex_str("84F280000A4F00E1E038480514461F00")?
+    );
+
+    let mut channel = SecureChannel::new(ScpVersion::Scp02, keys, SecurityLevel::CMacAndEncryption);
+    assert_eq!(
+        channel.external_authenticate(&cryptogram)?,
+        Vec::from_hex_str("8482030010AD10AA7691B724A86350B49597369BC4")?
+    );
+    assert_eq!(
+        channel.wrap(&Vec::from_hex_str("80F28000024F0000")?)?,
+        Vec::from_hex_str("84F280001073A3E36BC7A2A6F50ACAD3543EBB826D00")?
+    );
+    assert_eq!(
+        channel.encrypt_sensitive_data(&Vec::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?)?,
+        Vec::from_hex_str("BE0D985A44318D355C6AF9D7F42A920B")?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_invalid_apdu() -> Result<()> {
+    let keys = derive_scp02_keys(&static_keys()?, &[0x00, 0x0D])?;
+    let mut channel = SecureChannel::new(ScpVersion::Scp02, keys.clone(), SecurityLevel::CMac);
+    assert!(channel.wrap(&Vec::from_hex_str("80F280")?).is_err());
+    assert!(channel.wrap(&Vec::from_hex_str("80F28000034F00")?).is_err());
+
+    // C-MAC alone leaves room for 247 bytes, encryption pads them up to 248
+    let command = |len: u8| {
+        [0x80, 0xE2, 0x80, 0x00, len]
+            .into_iter()
+            .chain(vec![0; len as usize])
+            .collect::<Vec<u8>>()
+    };
+    assert_eq!(channel.wrap(&command(247))?.len(), 5 + 255);
+    assert!(matches!(
+        channel.wrap(&command(248)),
+        Err(Error::InvalidApdu(_))
+    ));
+    let mut channel = SecureChannel::new(ScpVersion::Scp02, keys, SecurityLevel::CMacAndEncryption);
+    assert_eq!(channel.wrap(&command(239))?.len(), 5 + 248);
+    assert!(matches!(
+        channel.wrap(&command(240)),
+        Err(Error::InvalidApdu(_))
+    ));
+    // case 1 and case 2 commands
+    assert_eq!(
+        channel.wrap(&Vec::from_hex_str("80CA9F7F")?)?.len(),
+        4 + 1 + 8
+    );
+    assert_eq!(
+        channel.wrap(&Vec::from_hex_str("80CA9F7F00")?)?.len(),
+        4 + 1 + 8 + 1
+    );
+    Ok(())
+}
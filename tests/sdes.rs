@@ -0,0 +1,46 @@
+use bitvec::prelude::*;
+use des_ndtp::{Result, SDes};
+use std::str::FromStr;
+
+fn bits(s: &str) -> BitVec {
+    s.chars().map(|ch| ch == '1').collect()
+}
+
+#[test]
+fn test_round_keys() -> Result<()> {
+    let cipher = SDes::from_str("1010000010")?;
+    let [k1, k2] = cipher.round_keys()?;
+    assert_eq!(k1, bits("10100100"));
+    assert_eq!(k2, bits("01000011"));
+    Ok(())
+}
+
+#[test]
+fn test_encode() -> Result<()> {
+    let cipher = SDes::from_str("1010000010")?;
+    assert_eq!(cipher.encode(&bits("10010111"))?, bits("00111000"));
+
+    let cipher = SDes::from_str("1100011110")?;
+    assert_eq!(cipher.encode(&bits("00101000"))?, bits("10001010"));
+    Ok(())
+}
+
+#[test]
+fn test_decode() -> Result<()> {
+    let cipher = SDes::from_str("1010000010")?;
+    assert_eq!(cipher.decode(&bits("00111000"))?, bits("10010111"));
+
+    let cipher = SDes::from_str("0111111101")?;
+    for block in 0..=255u8 {
+        let block = bits(&format!("{block:08b}"));
+        assert_eq!(cipher.decode(&cipher.encode(&block)?)?, block);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_invalid_lengths() {
+    assert!(SDes::from_str("101").is_err());
+    let cipher = SDes::from_str("1010000010").unwrap();
+    assert!(cipher.encode(&bits("1001011")).is_err());
+}
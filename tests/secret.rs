@@ -0,0 +1,52 @@
+use des_ndtp::{
+    Block, BlockCipher, FromHexStr, Gost28147, MainKey, Result, SecretKey, Subkey, TdesKey,
+};
+use zeroize::Zeroize;
+
+#[test]
+fn test_debug_is_redacted() -> Result<()> {
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    assert_eq!(format!("{key:?}"), "MainKey { key: <redacted> }");
+
+    let subkey = Subkey::from_hex_str("1B02EFFC7072")?;
+    assert_eq!(format!("{subkey:?}"), "Subkey { key: <redacted> }");
+
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    assert!(!format!("{key:?}").contains("0123"));
+
+    let key = Gost28147::from_hex_str(&"F0".repeat(32))?;
+    assert!(!format!("{key:?}").contains("1111"));
+
+    let key = SecretKey::new(MainKey::from_hex_str("133457799BBCDFF1")?);
+    assert_eq!(format!("{key:?}"), "SecretKey { key: <redacted> }");
+    Ok(())
+}
+
+#[test]
+fn test_zeroize() -> Result<()> {
+    let mut key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    key.zeroize();
+    assert!(key.as_bitvec().not_any());
+
+    let mut subkey = Subkey::from_hex_str("1B02EFFC7072")?;
+    subkey.zeroize();
+    assert!(subkey.as_bitvec().not_any());
+
+    let mut key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    key.zeroize();
+    assert!(key.components().iter().all(|key| key.as_bitvec().not_any()));
+    Ok(())
+}
+
+#[test]
+fn test_secret_key() -> Result<()> {
+    let key = MainKey::from_hex_str("0E329232EA6D0D73")?;
+    let block = Block::from_hex_str("8787878787878787")?;
+    let expected = block.encode(&key)?;
+
+    let key = SecretKey::new(key);
+    assert_eq!(key.encode_block(&block)?, expected);
+    assert_eq!(key.decode_block(&expected)?, block);
+    assert_eq!(block.encode(key.expose_secret())?, expected);
+    Ok(())
+}
@@ -1,4 +1,5 @@
-use des_ndtp::{Block, Result, ShiftSchemes};
+use bitvec::prelude::*;
+use des_ndtp::{permute, Block, Error, Result, ShiftSchemes};
 use std::str::FromStr;
 
 #[test]
@@ -26,3 +27,21 @@ fn test_ip1() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_permute_out_of_range() -> Result<()> {
+    let bits = bitvec![1, 0, 1, 1];
+    assert_eq!(permute(&[3, 1, 0], bits.clone())?, bitvec![1, 0, 1]);
+    assert!(matches!(
+        permute(&[0, 4], bits),
+        Err(Error::InvalidIterableLength {
+            expected: 5,
+            got: 4
+        })
+    ));
+    assert!(matches!(
+        permute(&[2], vec![7u8, 8]),
+        Err(Error::InvalidIterableLength { .. })
+    ));
+    Ok(())
+}
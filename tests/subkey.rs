@@ -0,0 +1,47 @@
+use des_ndtp::{Block, FromHexStr, MainKey, Result, Subkey, ToHexString};
+use std::str::FromStr;
+
+#[test]
+fn test_from_string() -> Result<()> {
+    let subkey = Subkey::from_str("000110110000001011101111111111000111000001110010")?;
+    assert_eq!(subkey.to_upper_hex(), "1B02EFFC7072");
+    assert_eq!(Subkey::from_hex_str("1B02EFFC7072")?, subkey);
+    assert_eq!(
+        subkey.to_string(),
+        "000110110000001011101111111111000111000001110010"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_invalid_width() {
+    assert!(Subkey::from_str("0001101100000010").is_err());
+    assert!(Subkey::from_hex_str("1B02EFFC707200").is_err());
+}
+
+#[test]
+fn test_key_schedule() -> Result<()> {
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    let subkeys = key.get_subkeys()?;
+    assert_eq!(subkeys[0], Subkey::from_hex_str("1B02EFFC7072")?);
+    assert_eq!(subkeys[15], Subkey::from_hex_str("CB3D8B0E17F5")?);
+
+    let plain_text = Block::from_hex_str("0123456789ABCDEF")?;
+    let cipher_text = plain_text.encode_with_subkeys(&subkeys)?;
+    assert_eq!(cipher_text, plain_text.encode(&key)?);
+    assert_eq!(cipher_text.decode_with_subkeys(&subkeys)?, plain_text);
+    Ok(())
+}
+
+#[test]
+fn test_independent_subkeys() -> Result<()> {
+    let subkeys: [Subkey; 16] = core::array::from_fn(|i| {
+        Subkey::from_hex_str(&format!("{:0>12X}", (i as u64 + 1) * 0x0F1E2D3C4B5A))
+            .expect("valid subkey")
+    });
+    let plain_text = Block::from_hex_str("0123456789ABCDEF")?;
+    let cipher_text = plain_text.encode_with_subkeys(&subkeys)?;
+    assert_ne!(cipher_text, plain_text);
+    assert_eq!(cipher_text.decode_with_subkeys(&subkeys)?, plain_text);
+    Ok(())
+}
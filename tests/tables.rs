@@ -0,0 +1,98 @@
+use des_ndtp::{
+    Block, DesTables, Error, FromHexStr, MainKey, Result, ShiftDirection, ShiftSchemes, ToHexString,
+};
+
+#[test]
+fn test_default_tables() -> Result<()> {
+    let tables = DesTables::default();
+    tables.validate()?;
+    for round in 1..=16 {
+        for direction in [ShiftDirection::Left, ShiftDirection::Right] {
+            assert_eq!(
+                tables.get_round_shift(round, direction)?,
+                direction.get_round_shift(round)? as usize
+            );
+        }
+    }
+
+    let plain_text = Block::from_hex_str("8787878787878787")?;
+    let key = MainKey::from_hex_str("0E329232EA6D0D73")?;
+    let cipher_text = plain_text.encode_with_tables(&key, &tables, 1..=16)?;
+    assert_eq!(cipher_text, plain_text.encode(&key)?);
+    assert_eq!(
+        cipher_text.decode_with_tables(&key, &tables, 1..=16)?,
+        plain_text
+    );
+    Ok(())
+}
+
+#[test]
+fn test_desl_tables() -> Result<()> {
+    let tables = DesTables::default()
+        .without_ip()
+        .with_single_s_box(ShiftSchemes::S1.as_slice().to_vec());
+    let plain_text = Block::from_hex_str("0123456789ABCDEF")?;
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    let cipher_text = plain_text.encode_with_tables(&key, &tables, 1..=16)?;
+    assert_eq!(cipher_text.to_upper_hex(), "2D4CDBEB03D7EC7E");
+    assert_eq!(
+        cipher_text.decode_with_tables(&key, &tables, 1..=16)?,
+        plain_text
+    );
+    Ok(())
+}
+
+#[test]
+fn test_s_box_order() -> Result<()> {
+    let tables = DesTables::default().with_s_box_order([7, 6, 5, 4, 3, 2, 1, 0])?;
+    assert_eq!(tables.s_boxes[0], ShiftSchemes::S8.as_slice());
+    assert_eq!(tables.s_boxes[7], ShiftSchemes::S1.as_slice());
+
+    let plain_text = Block::from_hex_str("0123456789ABCDEF")?;
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+    assert_eq!(
+        plain_text
+            .encode_with_tables(&key, &tables, 1..=16)?
+            .to_upper_hex(),
+        "CE1857D6296CCAEF"
+    );
+    let cipher_text = plain_text.encode_with_tables(&key, &tables, 1..=8)?;
+    assert_eq!(cipher_text.to_upper_hex(), "AB38926779296C30");
+    assert_eq!(
+        cipher_text.decode_with_tables(&key, &tables, 1..=8)?,
+        plain_text
+    );
+
+    assert!(DesTables::default()
+        .with_s_box_order([0, 0, 1, 2, 3, 4, 5, 6])
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_invalid_tables() -> Result<()> {
+    let block = Block::from_hex_str("0123456789ABCDEF")?;
+    let key = MainKey::from_hex_str("133457799BBCDFF1")?;
+
+    let mut tables = DesTables::default();
+    tables.e[0] = 32;
+    assert!(tables.validate().is_err());
+    assert!(block.encode_with_tables(&key, &tables, 1..=16).is_err());
+
+    let mut tables = DesTables::default();
+    tables.rotations.pop();
+    assert!(tables.validate().is_err());
+
+    // IP1 should be the inverse of IP, not just any permutation
+    let mut tables = DesTables::default();
+    tables.ip1.swap(0, 1);
+    assert!(matches!(tables.validate(), Err(Error::InvalidTable(name)) if name == "IP1"));
+    assert!(block.encode_with_tables(&key, &tables, 1..=16).is_err());
+    let tables = DesTables::default().without_ip();
+    tables.validate()?;
+
+    let mut tables = DesTables::default();
+    tables.s_boxes[3][10] = 16;
+    assert!(block.decode_with_tables(&key, &tables, 1..=16).is_err());
+    Ok(())
+}
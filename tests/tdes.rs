@@ -0,0 +1,76 @@
+use des_ndtp::{Block, FromHexStr, MainKey, Result, TdesKey, ToHexString};
+
+#[test]
+fn test_from_hex_string() -> Result<()> {
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    assert_eq!(key.component_count(), 2);
+    let [k1, k2, k3] = key.components();
+    assert_eq!(k1, &MainKey::from_hex_str("0123456789ABCDEF")?);
+    assert_eq!(k2, &MainKey::from_hex_str("FEDCBA9876543210")?);
+    assert_eq!(k3, k1);
+    assert_eq!(key.to_upper_hex(), "0123456789ABCDEFFEDCBA9876543210");
+
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA987654321089ABCDEF01234567")?;
+    assert_eq!(key.component_count(), 3);
+    assert_eq!(
+        key.components()[2],
+        &MainKey::from_hex_str("89ABCDEF01234567")?
+    );
+
+    assert!(TdesKey::from_hex_str("0123456789ABCDEF").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_parity() -> Result<()> {
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    assert!(key.has_odd_parity());
+
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543211")?;
+    assert!(!key.has_odd_parity());
+    let adjusted = key.with_odd_parity();
+    assert!(adjusted.has_odd_parity());
+    assert_eq!(adjusted.to_upper_hex(), "0123456789ABCDEFFEDCBA9876543210");
+    Ok(())
+}
+
+#[test]
+fn test_new_strict() -> Result<()> {
+    let k1 = MainKey::from_hex_str("0123456789ABCDEF")?;
+    let k2 = MainKey::from_hex_str("FEDCBA9876543210")?;
+    let bad = MainKey::from_hex_str("FEDCBA9876543211")?;
+    assert!(TdesKey::new_strict(vec![k1.clone(), k2.clone()]).is_ok());
+    assert!(TdesKey::new_strict(vec![k1.clone(), k2.clone(), k1.clone()]).is_ok());
+    assert!(TdesKey::new_strict(vec![k1.clone(), bad.clone()]).is_err());
+    assert!(TdesKey::new_strict(vec![k1.clone(), k2.clone(), bad]).is_err());
+    assert!(TdesKey::new(vec![k1]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_generate() -> Result<()> {
+    let key = TdesKey::generate(2)?;
+    assert_eq!(key.component_count(), 2);
+    assert!(key.has_odd_parity());
+    let [k1, k2, k3] = key.components();
+    assert_ne!(k1, k2);
+    assert_eq!(k1, k3);
+
+    let key = TdesKey::generate(3)?;
+    let [k1, k2, k3] = key.components();
+    assert!(k1 != k2 && k2 != k3 && k1 != k3);
+    assert!(key.components().iter().all(|key| key.weakness().is_none()));
+
+    assert!(TdesKey::generate(1).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_tdes_encode() -> Result<()> {
+    let key = TdesKey::from_hex_str("0123456789ABCDEFFEDCBA9876543210")?;
+    let block = Block::from_hex_str("0000000000000000")?;
+    let encoded = key.encode(&block)?;
+    assert_eq!(encoded.to_upper_hex(), "08D7B4FB629D0885");
+    assert_eq!(key.decode(&encoded)?, block);
+    Ok(())
+}
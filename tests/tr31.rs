@@ -0,0 +1,130 @@
+use des_ndtp::tr31::{unwrap, wrap, Tr31Header, Tr31Version};
+use des_ndtp::{Error, FromHexStr, Result, TdesKey};
+
+fn kbpks() -> Result<Vec<TdesKey>> {
+    Ok(vec![
+        TdesKey::from_hex_str("89E88CF7931444F334BD7547FC3F380C")?,
+        TdesKey::from_hex_str("0123456789ABCDEFFEDCBA987654321089ABCDEF01234567")?,
+    ])
+}
+
+#[test]
+fn test_round_trip() -> Result<()> {
+    let key = Vec::from_hex_str("F039121BEC83D26E169BDCEC5C4A1D33")?;
+    for kbpk in kbpks()? {
+        for version in [Tr31Version::A, Tr31Version::B, Tr31Version::C] {
+            let header = Tr31Header::new(version, "P0", 'T', 'E', 'E');
+            let block = wrap(&kbpk, &header, &key)?;
+            assert_eq!(
+                &block[..5],
+                &format!("{}{:04}", version.as_char(), block.len())
+            );
+            let (parsed, unwrapped) = unwrap(&kbpk, &block)?;
+            assert_eq!(parsed, header);
+            assert_eq!(*unwrapped, key);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_known_blocks() -> Result<()> {
+    // TR-31:2018 annex A examples
+    let kbpk = TdesKey::from_hex_str("89E88CF7931444F334BD7547FC3F380C")?;
+    let block = "A0072P0TE00E0000F5161ED902807AF26F1D62263644BD24192FDB3193C730301CEE8701";
+    let (header, key) = unwrap(&kbpk, block)?;
+    assert_eq!(header, Tr31Header::new(Tr31Version::A, "P0", 'T', 'E', 'E'));
+    assert_eq!(*key, Vec::from_hex_str("F039121BEC83D26B169BDCD5B22AAF8F")?);
+
+    let kbpk = TdesKey::from_hex_str("DD7515F2BFC17F85CE48F3CA25CB21F6")?;
+    let block = "B0080P0TE00E000094B420079CC80BA3461F86FE26EFC4A3B8E4FA4C5F5341176EED7B727B8A248E";
+    let (header, key) = unwrap(&kbpk, block)?;
+    assert_eq!(header, Tr31Header::new(Tr31Version::B, "P0", 'T', 'E', 'E'));
+    assert_eq!(*key, Vec::from_hex_str("3F419E1CB7079442AA37474C2EFBF8B8")?);
+
+    let kbpk = TdesKey::from_hex_str("B8ED59E0A279A295E9F5ED7944FD06B9")?;
+    let block = "C0096B0TX12S0100KS1800604B120F9292800000BFB9B689CB567E66FC3FEE5AD5F52161FC6545B9D60989015D02155C";
+    let (header, key) = unwrap(&kbpk, block)?;
+    assert_eq!(header.version, Tr31Version::C);
+    assert_eq!(header.key_usage, "B0");
+    assert_eq!(header.key_version, "12");
+    assert_eq!(header.exportability, 'S');
+    assert_eq!(
+        header.optional_blocks,
+        [("KS".to_string(), "00604B120F9292800000".to_string())]
+    );
+    assert_eq!(*key, Vec::from_hex_str("EDB380DD340BC2620247D445F5B8D678")?);
+    Ok(())
+}
+
+#[test]
+fn test_header_fields() -> Result<()> {
+    let kbpk = &kbpks()?[0];
+    let mut header = Tr31Header::new(Tr31Version::B, "K0", 'T', 'B', 'N');
+    header.key_version = "12".to_string();
+    header
+        .optional_blocks
+        .push(("TS".to_string(), "20261019120000Z".to_string()));
+    let block = wrap(kbpk, &header, &Vec::from_hex_str("0123456789ABCDEF")?)?;
+    // TS block is padded with a PB block to a multiple of 8
+    assert_eq!(&block[5..16], "K0TB12N0200");
+    assert_eq!(&block[16..40], "TS1320261019120000ZPB050");
+
+    let (parsed, key) = unwrap(kbpk, &block)?;
+    assert_eq!(parsed.key_usage, "K0");
+    assert_eq!(parsed.algorithm, 'T');
+    assert_eq!(parsed.mode_of_use, 'B');
+    assert_eq!(parsed.key_version, "12");
+    assert_eq!(parsed.exportability, 'N');
+    assert_eq!(parsed.optional_blocks, header.optional_blocks);
+    assert_eq!(*key, Vec::from_hex_str("0123456789ABCDEF")?);
+    Ok(())
+}
+
+#[test]
+fn test_tampered() -> Result<()> {
+    let kbpks = kbpks()?;
+    let key = Vec::from_hex_str("F039121BEC83D26E169BDCEC5C4A1D33")?;
+    for version in [Tr31Version::A, Tr31Version::B, Tr31Version::C] {
+        let header = Tr31Header::new(version, "P0", 'T', 'E', 'E');
+        let block = wrap(&kbpks[0], &header, &key)?;
+
+        let mut tampered = block.clone();
+        tampered.replace_range(8..9, "D");
+        assert!(matches!(
+            unwrap(&kbpks[0], &tampered),
+            Err(Error::MacMismatch)
+        ));
+
+        let last = if block.ends_with('0') { "1" } else { "0" };
+        let mut tampered = block.clone();
+        tampered.replace_range(block.len() - 1.., last);
+        assert!(matches!(
+            unwrap(&kbpks[0], &tampered),
+            Err(Error::MacMismatch)
+        ));
+
+        assert!(matches!(unwrap(&kbpks[1], &block), Err(Error::MacMismatch)));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_invalid_block() -> Result<()> {
+    let kbpk = &kbpks()?[0];
+    let header = Tr31Header::new(Tr31Version::C, "P0", 'T', 'E', 'E');
+    let block = wrap(kbpk, &header, &Vec::from_hex_str("0123456789ABCDEF")?)?;
+    assert!(unwrap(kbpk, &block[..block.len() - 2]).is_err());
+    assert!(unwrap(kbpk, &block.replacen('C', "D", 1)).is_err());
+    assert!(unwrap(kbpk, "C0016P0TE00E0000").is_err());
+
+    let header = Tr31Header::new(Tr31Version::C, "P", 'T', 'E', 'E');
+    assert!(wrap(kbpk, &header, &Vec::from_hex_str("0123456789ABCDEF")?).is_err());
+    assert!(wrap(
+        kbpk,
+        &Tr31Header::new(Tr31Version::C, "P0", 'T', 'E', 'E'),
+        &[]
+    )
+    .is_err());
+    Ok(())
+}
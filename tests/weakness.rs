@@ -0,0 +1,114 @@
+use bitvec::prelude::*;
+use des_ndtp::{Block, FromHexStr, KeyWeakness, MainKey, Result, ShiftSchemes, WeakKeyPolicy};
+
+/// builds a key from PC1 halves, parity bits set
+fn key_from_halves(left: &BitVec, right: &BitVec) -> MainKey {
+    let mut key = bitvec![0; 64];
+    for (bit, pos) in left
+        .iter()
+        .chain(right.iter())
+        .zip(ShiftSchemes::PC1.as_slice())
+    {
+        key.set(*pos, *bit);
+    }
+    MainKey::new(key).with_odd_parity()
+}
+
+#[test]
+fn test_weak_keys() -> Result<()> {
+    for key in [
+        "0101010101010101",
+        "FEFEFEFEFEFEFEFE",
+        "E0E0E0E0F1F1F1F1",
+        "1F1F1F1F0E0E0E0E",
+    ] {
+        let key = MainKey::from_hex_str(key)?;
+        assert_eq!(key.weakness(), Some(KeyWeakness::Weak));
+
+        let block = Block::from_hex_str("0123456789ABCDEF")?;
+        assert_eq!(block.encode(&key)?, block.decode(&key)?);
+    }
+    // parity bits are ignored
+    let key = MainKey::from_hex_str("0000000000000000")?;
+    assert_eq!(key.weakness(), Some(KeyWeakness::Weak));
+    Ok(())
+}
+
+#[test]
+fn test_semi_weak_keys() -> Result<()> {
+    for (key, dual) in [
+        ("01FE01FE01FE01FE", "FE01FE01FE01FE01"),
+        ("1FE01FE00EF10EF1", "E01FE01FF10EF10E"),
+        ("01E001E001F101F1", "E001E001F101F101"),
+        ("1FFE1FFE0EFE0EFE", "FE1FFE1FFE0EFE0E"),
+        ("011F011F010E010E", "1F011F010E010E01"),
+        ("E0FEE0FEF1FEF1FE", "FEE0FEE0FEF1FEF1"),
+    ] {
+        let key = MainKey::from_hex_str(key)?;
+        let dual = MainKey::from_hex_str(dual)?;
+        assert_eq!(
+            key.weakness(),
+            Some(KeyWeakness::SemiWeak { dual: dual.clone() })
+        );
+        assert_eq!(
+            dual.weakness(),
+            Some(KeyWeakness::SemiWeak { dual: key.clone() })
+        );
+
+        let block = Block::from_hex_str("0123456789ABCDEF")?;
+        assert_eq!(block.encode(&key)?.encode(&dual)?, block);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_category_sizes() {
+    let patterns: Vec<BitVec> = [
+        "0000", "1111", "0101", "1010", "0011", "0110", "1100", "1001",
+    ]
+    .iter()
+    .map(|pattern| pattern.repeat(7).chars().map(|ch| ch == '1').collect())
+    .collect();
+    let (mut weak, mut semi_weak, mut possibly_weak) = (0, 0, 0);
+    for left in &patterns {
+        for right in &patterns {
+            match key_from_halves(left, right).weakness() {
+                Some(KeyWeakness::Weak) => weak += 1,
+                Some(KeyWeakness::SemiWeak { .. }) => semi_weak += 1,
+                Some(KeyWeakness::PossiblyWeak) => possibly_weak += 1,
+                None => panic!("key should be weak"),
+            }
+        }
+    }
+    assert_eq!((weak, semi_weak, possibly_weak), (4, 12, 48));
+}
+
+#[test]
+fn test_possibly_weak_keys() -> Result<()> {
+    for key in ["1F1F01010E0E0101", "E0FE011FF1FE010E", "FE1F01E0FE0E01F1"] {
+        let key = MainKey::from_hex_str(key)?;
+        assert_eq!(key.weakness(), Some(KeyWeakness::PossiblyWeak));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_policy() -> Result<()> {
+    let strong = MainKey::from_hex_str("133457799BBCDFF1")?;
+    assert_eq!(strong.weakness(), None);
+    let weak = MainKey::from_hex_str("0101010101010101")?;
+    let possibly_weak = MainKey::from_hex_str("1F1F01010E0E0101")?;
+
+    assert!(strong
+        .check_weakness(WeakKeyPolicy::RejectPossiblyWeak)
+        .is_ok());
+    assert!(weak.check_weakness(WeakKeyPolicy::AllowAll).is_ok());
+    assert!(weak.check_weakness(WeakKeyPolicy::RejectWeak).is_err());
+    assert!(possibly_weak
+        .check_weakness(WeakKeyPolicy::RejectWeak)
+        .is_ok());
+    assert!(possibly_weak
+        .check_weakness(WeakKeyPolicy::RejectPossiblyWeak)
+        .is_err());
+    Ok(())
+}